@@ -1,55 +1,209 @@
-extern crate sdl2;
-use protracktor::ModPlayer;
-use sdl2::audio::{AudioCallback, AudioSpecDesired};
+use protracktor::{
+    AmigaModel, ChannelLayout, CompatibilityProfile, Interpolation, LoopMode, Module, PaulaClock,
+    Player, PlayerConfig, WavFormat, WavWriter,
+};
 use std::env;
-use std::fs;
-use std::io::Error;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::fs::{self, File};
+use std::io::{BufWriter, Error, ErrorKind};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
 
-struct SDLSound {
-    player: ModPlayer,
+const RENDER_USAGE: &str =
+    "Usage: cli render <module> <output.wav> [--float] [--rate HZ] [--ntsc] [--mono] [--separation 0-1] [--extended-panning] [--declick] [--profile pt23|pt1|ft] [--loops N, 0 = forever, needs --seconds] [--seconds S] [--fade S] [--stems] [--interpolation nearest|linear|hermite|sinc|blep] [--amiga a500|a1200]";
+
+struct RenderOptions {
+    format: WavFormat,
+    config: PlayerConfig,
+    loops: usize,
+    seconds: Option<f32>,
+    fade: f32,
+    stems: bool,
+    separation: Option<f32>,
+    compatibility: Option<CompatibilityProfile>,
 }
 
-impl AudioCallback for SDLSound {
-    type Channel = f32;
+fn usage_error(message: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("{}\n{}", message, RENDER_USAGE),
+    )
+}
 
-    fn callback(&mut self, out: &mut [Self::Channel]) {
-        self.player.render(out);
-    }
+fn parse_value<T: std::str::FromStr>(name: &str, value: Option<&String>) -> Result<T, Error> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| usage_error(&format!("{} needs a number", name)))
 }
 
-fn main() -> Result<(), Error> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() >= 2 {
-        let term = Arc::new(AtomicBool::new(false));
-        signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&term))?;
-        let module = fs::read(&args[1]).unwrap();
-        let player: ModPlayer = ModPlayer::load(module);
-        println!("PLAYING: {}", player.name);
-        println!("Samples: {}", player.samples.len());
-
-        let sdl_context = sdl2::init().unwrap();
-        let audio_subsystem = sdl_context.audio().expect("Audio system failed");
-
-        let desired_spec = AudioSpecDesired {
-            freq: Some(48_000),
-            channels: Some(2), // mono
-            samples: None,     // default sample size
-        };
-        let device = audio_subsystem
-            .open_playback(None, &desired_spec, |spec| {
-                println!("Spec: {:?}", spec);
-                SDLSound { player }
-            })
-            .expect("Device open failed");
-        device.resume();
-        while !term.load(Ordering::Relaxed) {
-            std::thread::sleep(Duration::from_millis(200));
+/// Renders a module to a WAV file without opening an audio device.
+fn render(args: &[String]) -> Result<(), Error> {
+    let mut paths = Vec::new();
+    let mut options = RenderOptions {
+        format: WavFormat::Int16,
+        config: PlayerConfig::default(),
+        loops: 1,
+        seconds: None,
+        fade: 0.0,
+        stems: false,
+        separation: None,
+        compatibility: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--float" => options.format = WavFormat::Float32,
+            "--rate" => options.config.output_rate = parse_value(arg, args.next())?,
+            "--ntsc" => options.config.paula_clock = PaulaClock::Ntsc,
+            "--mono" => options.config.channel_layout = ChannelLayout::Mono,
+            "--extended-panning" => options.config.extended_panning = true,
+            "--declick" => options.config.declick = true,
+            "--profile" => {
+                options.compatibility = match args.next().map(String::as_str) {
+                    Some("pt23") => Some(CompatibilityProfile::ProTracker23),
+                    Some("pt1") => Some(CompatibilityProfile::ProTracker1),
+                    Some("ft") => Some(CompatibilityProfile::FastTracker),
+                    _ => return Err(usage_error("Unknown compatibility profile")),
+                }
+            }
+            "--separation" => options.separation = Some(parse_value(arg, args.next())?),
+            "--loops" => options.loops = parse_value(arg, args.next())?,
+            "--seconds" => options.seconds = Some(parse_value(arg, args.next())?),
+            "--fade" => options.fade = parse_value(arg, args.next())?,
+            "--stems" => options.stems = true,
+            "--amiga" => {
+                options.config.amiga_model = match args.next().map(String::as_str) {
+                    Some("a500") => Some(AmigaModel::A500),
+                    Some("a1200") => Some(AmigaModel::A1200),
+                    _ => return Err(usage_error("Unknown Amiga model")),
+                }
+            }
+            "--interpolation" => {
+                options.config.interpolation = match args.next().map(String::as_str) {
+                    Some("nearest") => Interpolation::Nearest,
+                    Some("linear") => Interpolation::Linear,
+                    Some("hermite") => Interpolation::Hermite,
+                    Some("sinc") => Interpolation::Sinc,
+                    Some("blep") => Interpolation::Blep,
+                    _ => return Err(usage_error("Unknown interpolation")),
+                }
+            }
+            _ if arg.starts_with("--") => {
+                return Err(usage_error(&format!("Unknown option {}", arg)))
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() != 2 {
+        return Err(usage_error("Expected a module and an output file"));
+    }
+    // a song playing forever would fill the disk
+    if options.loops == 0 && options.seconds.is_none() {
+        return Err(usage_error("--loops 0 needs --seconds"));
+    }
+
+    let module = fs::read(paths[0])?;
+    let mut module =
+        Module::load_lenient(&module).map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+    if let Some(compatibility) = options.compatibility {
+        module.compatibility = compatibility;
+    }
+    options.config.loop_mode = match options.loops {
+        0 => LoopMode::Forever,
+        1 => LoopMode::StopAtEnd,
+        loops => LoopMode::Repeat(loops - 1),
+    };
+    let mut player = Player::with_config(Arc::new(module), options.config);
+    if let Some(separation) = options.separation {
+        player.set_stereo_separation(separation);
+    }
+    let rate = player.sample_rate();
+    let channels = options.config.channel_layout.channels();
+    // one stereo file, or one mono file per channel
+    let mut writers = Vec::new();
+    if options.stems {
+        for ch in 0..player.module().channel_count() {
+            let output = BufWriter::new(File::create(stem_path(paths[1], ch))?);
+            writers.push(WavWriter::new(output, rate, 1, options.format)?);
         }
     } else {
-        panic!("No module given");
+        let output = BufWriter::new(File::create(paths[1])?);
+        writers.push(WavWriter::new(output, rate, channels, options.format)?);
+    }
+    let width = if options.stems { 1 } else { channels };
+    let mut bufs = vec![vec![0.0; width * 1024]; writers.len()];
+
+    let limit = options
+        .seconds
+        .map(|seconds| (seconds * rate as f32) as usize);
+    let mut rendered = 0;
+    while !player.is_finished() && limit.is_none_or(|limit| rendered < limit) {
+        let frames = limit.map_or(1024, |limit| (limit - rendered).min(1024));
+        // the last block stops where the song does
+        let frames = render_block(&mut player, &mut bufs, width * frames, options.stems);
+        for (writer, buf) in writers.iter_mut().zip(&bufs) {
+            writer.write(&buf[..width * frames])?;
+        }
+        rendered += frames;
+    }
+
+    // keep playing past the end while fading out
+    options.config.loop_mode = LoopMode::Forever;
+    player.set_config(options.config);
+    let fade_frames = (options.fade * rate as f32) as usize;
+    let mut faded = 0;
+    while faded < fade_frames {
+        let frames = (fade_frames - faded).min(1024);
+        render_block(&mut player, &mut bufs, width * frames, options.stems);
+        for (writer, buf) in writers.iter_mut().zip(bufs.iter_mut()) {
+            for (index, frame) in buf[..width * frames].chunks_mut(width).enumerate() {
+                let gain = 1.0 - (faded + index) as f32 / fade_frames as f32;
+                for sample in frame {
+                    *sample *= gain;
+                }
+            }
+            writer.write(&buf[..width * frames])?;
+        }
+        faded += frames;
+    }
+
+    for writer in writers {
+        writer.finish()?;
     }
     Ok(())
 }
+
+/// Renders the next `len` samples into the first of `bufs`, or into one
+/// buffer per channel for stems. Returns the number of frames played.
+fn render_block(player: &mut Player, bufs: &mut [Vec<f32>], len: usize, stems: bool) -> usize {
+    if stems {
+        let mut stems: Vec<&mut [f32]> = bufs.iter_mut().map(|buf| &mut buf[..len]).collect();
+        player.render_channels(&mut stems)
+    } else {
+        player.render(&mut bufs[0][..len])
+    }
+}
+
+/// `song.wav` becomes `song.1.wav` for the first channel.
+fn stem_path(path: &str, ch: usize) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(extension) => path.with_file_name(format!(
+            "{}.{}.{}",
+            stem,
+            ch + 1,
+            extension.to_string_lossy()
+        )),
+        None => path.with_file_name(format!("{}.{}", stem, ch + 1)),
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() >= 2 && args[1] == "render" {
+        return render(&args[2..]);
+    }
+    Err(usage_error(
+        "cli only renders, play modules with the protracktor binary",
+    ))
+}
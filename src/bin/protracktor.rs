@@ -1,36 +1,31 @@
-extern crate sdl2;
-use protracktor::ModPlayer;
-use sdl2::audio::{AudioCallback, AudioSpecDesired};
+use protracktor::{AudioSink, ChannelLayout, Player, SdlSink};
 use std::env;
 use std::fs;
-use std::io::Error;
+use std::io::{Error, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-struct SDLSound {
-    player: ModPlayer,
-}
-
-impl AudioCallback for SDLSound {
-    type Channel = f32;
-
-    fn callback(&mut self, out: &mut [Self::Channel]) {
-        self.player.render(out);
-    }
-}
-
 fn main() -> Result<(), Error> {
     let args: Vec<String> = env::args().collect();
-    if args.len() >= 2 {
+    // --mono asks the device for a single channel
+    let mono = args.iter().any(|arg| arg == "--mono");
+    let path = args[1..].iter().find(|arg| !arg.starts_with("--"));
+    if let Some(path) = path {
         let term = Arc::new(AtomicBool::new(false));
         signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&term))?;
-        let module = fs::read(&args[1]).unwrap();
-        let player: ModPlayer = ModPlayer::load(module);
-        println!("LOADING: {}", player.name);
+        let module = fs::read(path).unwrap();
+        let player: Player = match Player::load_lenient(module) {
+            Ok(player) => player,
+            Err(error) => {
+                eprintln!("Could not load {}: {}", path, error);
+                std::process::exit(1);
+            }
+        };
+        println!("LOADING: {}", player.module().name);
 
-        for sample in &player.samples {
-            if sample.length > 0 {
+        for sample in player.module().samples() {
+            if sample.length() > 0 {
                 println!("{}", sample.name);
             }
         }
@@ -38,22 +33,33 @@ fn main() -> Result<(), Error> {
         let sdl_context = sdl2::init().unwrap();
         let audio_subsystem = sdl_context.audio().expect("Audio system failed");
 
-        let desired_spec = AudioSpecDesired {
-            freq: Some(48_000),
-            channels: Some(2), // mono
-            samples: None,     // default sample size
+        let channel_layout = if mono {
+            ChannelLayout::Mono
+        } else {
+            ChannelLayout::Stereo
         };
-        let device = audio_subsystem
-            .open_playback(None, &desired_spec, |spec| {
-                println!("Spec: {:?}", spec);
-                SDLSound { player }
-            })
+        let mut sink = SdlSink::open(&audio_subsystem, player.sample_rate(), channel_layout)
             .expect("Device open failed");
+        println!("Format: {:?}", sink.format());
+        let mut handle = player.play_on(&mut sink)?;
         println!("PLAYING");
-        device.resume();
+        let mut position = None;
         while !term.load(Ordering::Relaxed) {
-            std::thread::sleep(Duration::from_millis(200));
+            let state = *handle.state();
+            if state.finished {
+                break;
+            }
+            if position != Some(state.position) {
+                position = Some(state.position);
+                print!(
+                    "\rPosition {:3} Row {:2}",
+                    state.position.0, state.position.1
+                );
+                std::io::stdout().flush()?;
+            }
+            std::thread::sleep(Duration::from_millis(20));
         }
+        println!();
     } else {
         panic!("No module given");
     }
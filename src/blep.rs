@@ -0,0 +1,169 @@
+//! Band-limited steps for synthesising Paula's zero-order hold output.
+//!
+//! Paula holds every sample byte until the next one is fetched, so the
+//! output of a voice is a staircase of steps. Each step is rendered as a
+//! minimum-phase band-limited step (minBLEP, after Eli Brandt): the voice
+//! outputs the naive staircase and a ring buffer adds the difference between
+//! the band-limited and the hard step over the following output samples.
+
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+/// Zero crossings of the windowed sinc on either side of its centre.
+const ZERO_CROSSINGS: usize = 8;
+/// Table entries per output sample.
+const OVERSAMPLING: usize = 64;
+/// Output samples a single step takes to settle.
+pub(crate) const BLEP_LENGTH: usize = 2 * ZERO_CROSSINGS;
+const FFT_SIZE: usize = 8192;
+
+/// Difference between the band-limited and the hard step, `OVERSAMPLING`
+/// entries per output sample plus a trailing zero.
+fn residual_table() -> &'static [f32] {
+    static TABLE: OnceLock<Vec<f32>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let length = BLEP_LENGTH * OVERSAMPLING;
+        // Blackman windowed sinc, cut off at the output Nyquist frequency
+        let impulse: Vec<f64> = (0..length)
+            .map(|i| {
+                let x = (i as f64 - (length / 2) as f64) / OVERSAMPLING as f64;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (PI * x).sin() / (PI * x)
+                };
+                let phase = 2.0 * PI * i as f64 / (length - 1) as f64;
+                sinc * (0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos())
+            })
+            .collect();
+
+        let impulse = minimum_phase(&impulse);
+        let total: f64 = impulse.iter().sum();
+        let mut step = 0.0;
+        let mut table: Vec<f32> = impulse
+            .iter()
+            .map(|value| {
+                step += value / total;
+                (step - 1.0) as f32
+            })
+            .collect();
+        table.push(0.0);
+        table
+    })
+}
+
+/// Turns `impulse` into the minimum-phase impulse with the same magnitude
+/// response, by folding its real cepstrum.
+fn minimum_phase(impulse: &[f64]) -> Vec<f64> {
+    let mut re = vec![0.0; FFT_SIZE];
+    let mut im = vec![0.0; FFT_SIZE];
+    re[..impulse.len()].copy_from_slice(impulse);
+    fft(&mut re, &mut im, false);
+    for (re, im) in re.iter_mut().zip(im.iter_mut()) {
+        *re = re.hypot(*im).max(1e-100).ln();
+        *im = 0.0;
+    }
+    fft(&mut re, &mut im, true);
+
+    for i in 1..FFT_SIZE / 2 {
+        re[i] *= 2.0;
+        im[i] *= 2.0;
+    }
+    for i in FFT_SIZE / 2 + 1..FFT_SIZE {
+        re[i] = 0.0;
+        im[i] = 0.0;
+    }
+    fft(&mut re, &mut im, false);
+    for (re, im) in re.iter_mut().zip(im.iter_mut()) {
+        let magnitude = re.exp();
+        (*re, *im) = (magnitude * im.cos(), magnitude * im.sin());
+    }
+    fft(&mut re, &mut im, true);
+    re.truncate(impulse.len());
+    re
+}
+
+/// In-place radix-2 FFT, scaled by `1 / n` when `inverse`.
+fn fft(re: &mut [f64], im: &mut [f64], inverse: bool) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut size = 2;
+    while size <= n {
+        let angle = sign * 2.0 * PI / size as f64;
+        for start in (0..n).step_by(size) {
+            for k in 0..size / 2 {
+                let (w_im, w_re) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + size / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        size *= 2;
+    }
+
+    if inverse {
+        for (re, im) in re.iter_mut().zip(im.iter_mut()) {
+            *re /= n as f64;
+            *im /= n as f64;
+        }
+    }
+}
+
+/// Corrections still to be added to the next output samples of a voice.
+#[derive(Clone)]
+pub(crate) struct Blep {
+    buffer: [f32; BLEP_LENGTH],
+    index: usize,
+}
+
+impl Blep {
+    pub(crate) fn new() -> Blep {
+        Blep {
+            buffer: [0.0; BLEP_LENGTH],
+            index: 0,
+        }
+    }
+
+    /// Adds a step of `amplitude` that happened `offset` output samples
+    /// (0.0 to 1.0) before the current one.
+    pub(crate) fn add(&mut self, offset: f32, amplitude: f32) {
+        let table = residual_table();
+        let position = offset * OVERSAMPLING as f32;
+        let mut index = position as usize;
+        let fac = position - index as f32;
+        for k in 0..BLEP_LENGTH {
+            if index + 1 >= table.len() {
+                break;
+            }
+            let residual = table[index] * (1.0 - fac) + table[index + 1] * fac;
+            self.buffer[(self.index + k) % BLEP_LENGTH] += amplitude * residual;
+            index += OVERSAMPLING;
+        }
+    }
+
+    /// Takes the correction for the current output sample and moves on.
+    pub(crate) fn next(&mut self) -> f32 {
+        let value = self.buffer[self.index];
+        self.buffer[self.index] = 0.0;
+        self.index = (self.index + 1) % BLEP_LENGTH;
+        value
+    }
+}
@@ -0,0 +1,113 @@
+/// Master clock Paula derives its sample rates from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaulaClock {
+    /// European machines, 3546895 Hz
+    Pal,
+    /// American machines, 3579545 Hz
+    Ntsc,
+}
+
+impl PaulaClock {
+    pub fn hz(&self) -> usize {
+        match self {
+            PaulaClock::Pal => 3546895,
+            PaulaClock::Ntsc => 3579545,
+        }
+    }
+}
+
+/// Layout of the frames [`Player::render`](crate::Player::render) writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelLayout {
+    /// One sample per frame, all channels mixed down.
+    Mono,
+    /// Interleaved left and right samples.
+    Stereo,
+}
+
+impl ChannelLayout {
+    pub fn channels(&self) -> usize {
+        match self {
+            ChannelLayout::Mono => 1,
+            ChannelLayout::Stereo => 2,
+        }
+    }
+}
+
+/// What a [`Player`](crate::Player) does once the song has reached its end,
+/// either by running past the last position or by jumping back to a row
+/// played before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Keep playing the song over and over, like ProTracker does.
+    Forever,
+    /// Stop after playing the song once.
+    StopAtEnd,
+    /// Play the song, then repeat it this many times before stopping.
+    Repeat(usize),
+}
+
+/// How a voice computes output samples in between the bytes of its sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Holds every byte until the next one, like Paula does. Bright and
+    /// aliased, the hard sound of a real Amiga.
+    Nearest,
+    /// Straight lines between neighbouring bytes.
+    Linear,
+    /// Cubic Hermite (Catmull-Rom) spline through four bytes.
+    Hermite,
+    /// Lanczos windowed sinc over eight bytes, the smoothest of the
+    /// interpolating modes.
+    Sinc,
+    /// Paula's hold steps synthesised as minimum-phase band-limited steps
+    /// (minBLEP) timed to the Paula clock: the sound of the hardware without
+    /// the aliasing of [`Interpolation::Nearest`].
+    Blep,
+}
+
+/// Amiga whose analog output filters are emulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmigaModel {
+    /// Low-pass at about 4.4 kHz, plus the LED filter.
+    A500,
+    /// Low-pass at about 34 kHz, plus the LED filter.
+    A1200,
+}
+
+/// Output settings of a [`Player`](crate::Player).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerConfig {
+    /// Output sample rate in Hz.
+    pub output_rate: usize,
+    pub paula_clock: PaulaClock,
+    pub channel_layout: ChannelLayout,
+    pub loop_mode: LoopMode,
+    pub interpolation: Interpolation,
+    /// Emulates the output filters of this model, including the LED filter
+    /// switched by `E0x`. `None` leaves the output unfiltered.
+    pub amiga_model: Option<AmigaModel>,
+    /// Lets `8xx` (00 left to FF right) and `E8x` (0 to F) pan channels, as
+    /// in many PC-made modules. ProTracker ignores both effects, so this is
+    /// off by default.
+    pub extended_panning: bool,
+    /// Ramps volume changes over a few milliseconds and briefly fades out
+    /// the previous note when a new one starts, which avoids clicks but
+    /// softens ProTracker's sharp attacks. Off by default.
+    pub declick: bool,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> PlayerConfig {
+        PlayerConfig {
+            output_rate: 48000,
+            paula_clock: PaulaClock::Pal,
+            channel_layout: ChannelLayout::Stereo,
+            loop_mode: LoopMode::Forever,
+            interpolation: Interpolation::Linear,
+            amiga_model: None,
+            extended_panning: false,
+            declick: false,
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Analog output stage of the Amiga, applied to the mixed output.
+
+use crate::config::AmigaModel;
+use std::f32::consts::PI;
+
+/// Cutoff of the RC high-pass both models have in the output (1390 ohm,
+/// 22 uF).
+const HIGH_PASS_HZ: f32 = 5.2;
+/// Cutoff of the "LED" filter, a Sallen-Key low-pass with 10 kohm
+/// resistors and 6800 pF and 3900 pF capacitors.
+const LED_HZ: f32 = 3090.5;
+const LED_Q: f32 = 0.660;
+
+impl AmigaModel {
+    /// Cutoff of the fixed RC low-pass: 360 ohm and 0.1 uF on the A500,
+    /// 680 ohm and 6800 pF on the A1200.
+    fn low_pass_hz(&self) -> f32 {
+        match self {
+            AmigaModel::A500 => 4420.9,
+            AmigaModel::A1200 => 34419.0,
+        }
+    }
+}
+
+/// First order low-pass, `y += a * (x - y)`.
+#[derive(Clone, Copy)]
+struct OnePole {
+    a: f32,
+    y: f32,
+}
+
+impl OnePole {
+    fn new(cutoff: f32, rate: f32) -> OnePole {
+        OnePole {
+            a: 1.0 - (-2.0 * PI * cutoff / rate).exp(),
+            y: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        self.y += self.a * (x - self.y);
+        self.y
+    }
+}
+
+/// Second order low-pass, a bilinear transform of the analog prototype.
+#[derive(Clone, Copy)]
+struct TwoPole {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl TwoPole {
+    fn new(cutoff: f32, q: f32, rate: f32) -> TwoPole {
+        let w0 = 2.0 * PI * cutoff.min(rate * 0.45) / rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        let b1 = (1.0 - w0.cos()) / a0;
+        TwoPole {
+            b0: b1 * 0.5,
+            b1,
+            b2: b1 * 0.5,
+            a1: -2.0 * w0.cos() / a0,
+            a2: (1.0 - alpha) / a0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Filters of one output channel.
+#[derive(Clone, Copy)]
+struct ChannelFilter {
+    low_pass: OnePole,
+    led: TwoPole,
+    high_pass: OnePole,
+}
+
+/// Fixed low-pass, switchable LED filter and DC blocking high-pass of an
+/// Amiga model, in that order, for every output channel.
+pub(crate) struct AmigaFilter {
+    channels: Vec<ChannelFilter>,
+}
+
+impl AmigaFilter {
+    pub(crate) fn new(model: AmigaModel, rate: usize, channels: usize) -> AmigaFilter {
+        let rate = rate as f32;
+        let filter = ChannelFilter {
+            low_pass: OnePole::new(model.low_pass_hz(), rate),
+            led: TwoPole::new(LED_HZ, LED_Q, rate),
+            high_pass: OnePole::new(HIGH_PASS_HZ, rate),
+        };
+        AmigaFilter {
+            channels: vec![filter; channels],
+        }
+    }
+
+    /// Filters interleaved frames in place. The LED filter keeps running
+    /// while it is off so switching it on does not start from silence.
+    pub(crate) fn process(&mut self, buf: &mut [f32], led: bool) {
+        let channels = self.channels.len();
+        for frame in buf.chunks_mut(channels) {
+            for (sample, filter) in frame.iter_mut().zip(self.channels.iter_mut()) {
+                let mut value = filter.low_pass.process(*sample);
+                let filtered = filter.led.process(value);
+                if led {
+                    value = filtered;
+                }
+                *sample = value - filter.high_pass.process(value);
+            }
+        }
+    }
+}
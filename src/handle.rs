@@ -0,0 +1,179 @@
+//! Control of a player that lives in an audio callback. The callback owns a
+//! [`PlayerRenderer`], any other thread talks to it through the matching
+//! [`PlayerHandle`]. Commands travel through a lock-free queue and the state
+//! through a triple buffer, so the callback never waits for the controlling
+//! thread.
+
+use crate::config::PlayerConfig;
+use crate::module::Module;
+use crate::player::Player;
+use crate::spsc::{self, Consumer, Producer};
+use crate::triple::{self, Reader, Writer};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The most channels a module can have.
+const MAX_CHANNELS: usize = 32;
+const COMMAND_CAPACITY: usize = 64;
+
+/// Changes sent from a [`PlayerHandle`], applied by the renderer before the
+/// next block it renders. They correspond to the [`Player`] methods of the
+/// same name. Commands for channels the module does not have are ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// Renders silence without advancing the song
+    Pause,
+    Resume,
+    SeekToOrder {
+        order: usize,
+        row: usize,
+    },
+    SeekToTime(Duration),
+    SetChannelMuted(usize, bool),
+    SetChannelSolo(usize, bool),
+    SetChannelGain(usize, f32),
+    SetChannelPan(usize, f32),
+    SetStereoSeparation(f32),
+    SetConfig(PlayerConfig),
+}
+
+/// What the renderer played last, as seen by the [`PlayerHandle`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaybackState {
+    /// Position and row the next tick will play
+    pub position: (usize, usize),
+    pub paused: bool,
+    pub finished: bool,
+    levels: [f32; MAX_CHANNELS],
+    channel_count: usize,
+}
+
+impl PlaybackState {
+    fn of(player: &Player, paused: bool) -> PlaybackState {
+        let channel_count = player.module().channel_count.min(MAX_CHANNELS);
+        let mut levels = [0.0; MAX_CHANNELS];
+        if !paused {
+            for (ch, level) in levels[..channel_count].iter_mut().enumerate() {
+                *level = player.channel_level(ch);
+            }
+        }
+        PlaybackState {
+            position: player.position(),
+            paused,
+            finished: player.is_finished(),
+            levels,
+            channel_count,
+        }
+    }
+
+    /// Peak level of every channel in the last rendered block, as returned
+    /// by [`Player::channel_level`].
+    pub fn levels(&self) -> &[f32] {
+        &self.levels[..self.channel_count]
+    }
+}
+
+/// Controls a [`PlayerRenderer`] from another thread.
+pub struct PlayerHandle {
+    module: Arc<Module>,
+    commands: Producer<Command>,
+    state: Reader<PlaybackState>,
+}
+
+impl PlayerHandle {
+    /// Queues `command` for the renderer. Hands it back if the renderer has
+    /// not caught up with the commands sent before.
+    pub fn send(&mut self, command: Command) -> Result<(), Command> {
+        self.commands.push(command)
+    }
+
+    /// The state the renderer reported last.
+    pub fn state(&mut self) -> &PlaybackState {
+        self.state.read()
+    }
+
+    /// The song the renderer plays.
+    pub fn module(&self) -> &Arc<Module> {
+        &self.module
+    }
+}
+
+/// The half of a split [`Player`] that renders, meant to be moved into the
+/// audio callback.
+pub struct PlayerRenderer {
+    player: Player,
+    commands: Consumer<Command>,
+    state: Writer<PlaybackState>,
+    paused: bool,
+}
+
+impl PlayerRenderer {
+    /// Applies the queued commands, fills `buf` like [`Player::render`] and
+    /// reports the new state to the handle. Returns the number of frames
+    /// played, counting silence while paused.
+    pub fn render(&mut self, buf: &mut [f32]) -> usize {
+        while let Some(command) = self.commands.pop() {
+            self.apply(command);
+        }
+        let frames = if self.paused {
+            buf.fill(0.0);
+            buf.len() / self.player.config().channel_layout.channels()
+        } else {
+            self.player.render(buf)
+        };
+        self.state
+            .write(PlaybackState::of(&self.player, self.paused));
+        frames
+    }
+
+    fn apply(&mut self, command: Command) {
+        let player = &mut self.player;
+        let channel_count = player.module().channel_count;
+        match command {
+            // a bad channel from the controlling thread must not panic here
+            Command::SetChannelMuted(ch, _)
+            | Command::SetChannelSolo(ch, _)
+            | Command::SetChannelGain(ch, _)
+            | Command::SetChannelPan(ch, _)
+                if ch >= channel_count => {}
+            Command::Pause => self.paused = true,
+            Command::Resume => self.paused = false,
+            Command::SeekToOrder { order, row } => {
+                player.seek_to_order(order, row);
+            }
+            Command::SeekToTime(time) => player.seek_to_time(time),
+            Command::SetChannelMuted(ch, muted) => player.set_channel_muted(ch, muted),
+            Command::SetChannelSolo(ch, solo) => player.set_channel_solo(ch, solo),
+            Command::SetChannelGain(ch, gain) => player.set_channel_gain(ch, gain),
+            Command::SetChannelPan(ch, pan) => player.set_channel_pan(ch, pan),
+            Command::SetStereoSeparation(separation) => player.set_stereo_separation(separation),
+            Command::SetConfig(config) => player.set_config(config),
+        }
+    }
+
+    /// The player being rendered, e.g. to read its configuration.
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+}
+
+impl Player {
+    /// Splits the player into a renderer for the audio callback and a
+    /// handle to control it from elsewhere.
+    pub fn split(self) -> (PlayerHandle, PlayerRenderer) {
+        let (command_producer, command_consumer) = spsc::queue(COMMAND_CAPACITY);
+        let (state_writer, state_reader) = triple::buffer(PlaybackState::of(&self, false));
+        let handle = PlayerHandle {
+            module: self.module().clone(),
+            commands: command_producer,
+            state: state_reader,
+        };
+        let renderer = PlayerRenderer {
+            player: self,
+            commands: command_consumer,
+            state: state_writer,
+            paused: false,
+        };
+        (handle, renderer)
+    }
+}
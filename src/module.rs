@@ -0,0 +1,934 @@
+use std::cmp;
+use std::error::Error;
+use std::fmt;
+
+/// The tracker family a module was written with, as far as the loader can
+/// tell from the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleFormat {
+    /// 15 samples, with effect 1 as arpeggio, effect 2 as pitch bend, loop
+    /// starts in bytes and the tempo stored in the header. Recognised by its
+    /// loop starts; effects and loops are converted to their ProTracker
+    /// equivalents on load.
+    UltimateSoundtracker,
+    /// 15 samples with the later Soundtracker effects, but no extended
+    /// effects and no BPM tempo.
+    Soundtracker,
+    /// 31 samples and a tag at offset 1080.
+    ProTracker,
+}
+
+impl ModuleFormat {
+    pub fn is_soundtracker(&self) -> bool {
+        matches!(
+            self,
+            ModuleFormat::UltimateSoundtracker | ModuleFormat::Soundtracker
+        )
+    }
+}
+
+/// Replayer whose quirks a [`Player`](crate::Player) follows where trackers
+/// disagree about effects.
+///
+/// | Quirk | ProTracker 2.3D | ProTracker 1.x | FastTracker |
+/// |---|---|---|---|
+/// | `E9x` on the first tick of a row without a note | retriggers | retriggers | waits |
+/// | Vibrato depth | normal | doubled | normal |
+/// | `9xx` past the sample end | plays the loop | plays the loop | cuts the note |
+/// | `Dxx` left of `Bxx` on the same row | breaks to row 0 | breaks to row 0 | keeps its row |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatibilityProfile {
+    ProTracker23,
+    ProTracker1,
+    FastTracker,
+}
+
+impl CompatibilityProfile {
+    /// Guesses the tracker a module was made with: Soundtracker modules get
+    /// the old ProTracker behaviour, PC channel tags like `6CHN` or `16CH`
+    /// point to FastTracker, anything else is ProTracker 2.3D. The panning
+    /// effects `8xx` and `E8x` are no hint, since Amiga demos use them as
+    /// sync triggers.
+    fn detect(format: ModuleFormat, tag: &[u8]) -> CompatibilityProfile {
+        if format.is_soundtracker() {
+            return CompatibilityProfile::ProTracker1;
+        }
+        if tag.ends_with(b"CHN") || tag.ends_with(b"CH") || tag.ends_with(b"CN") {
+            CompatibilityProfile::FastTracker
+        } else {
+            CompatibilityProfile::ProTracker23
+        }
+    }
+
+    /// Whether `E9x` retriggers on the first tick of rows without a note.
+    pub(crate) fn retriggers_on_first_tick(&self) -> bool {
+        *self != CompatibilityProfile::FastTracker
+    }
+
+    /// Factor applied to the vibrato depth.
+    pub(crate) fn vibrato_depth(&self) -> isize {
+        match self {
+            CompatibilityProfile::ProTracker1 => 2,
+            _ => 1,
+        }
+    }
+
+    /// Whether `9xx` past the end of the sample cuts the note instead of
+    /// playing its loop.
+    pub(crate) fn cuts_offset_past_end(&self) -> bool {
+        *self == CompatibilityProfile::FastTracker
+    }
+
+    /// Whether `Bxx` cancels the row of a `Dxx` in an earlier channel.
+    pub(crate) fn position_jump_clears_break(&self) -> bool {
+        *self != CompatibilityProfile::FastTracker
+    }
+}
+
+/// Why a module could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// The file ends before the song header does.
+    TooShort { length: usize, required: usize },
+    /// The header contains values no tracker would write.
+    BadHeader(&'static str),
+    /// A pattern extends past the end of the file.
+    PatternDataPastEof { pattern: usize },
+    /// A sample's PCM data extends past the end of the file. Loading leniently
+    /// zero-pads the sample instead.
+    SampleDataPastEof { sample: usize, missing: usize },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::TooShort { length, required } => write!(
+                f,
+                "file too short: {} bytes, at least {} required",
+                length, required
+            ),
+            LoadError::BadHeader(reason) => write!(f, "bad module header: {}", reason),
+            LoadError::PatternDataPastEof { pattern } => {
+                write!(f, "pattern {} extends past end of file", pattern)
+            }
+            LoadError::SampleDataPastEof { sample, missing } => write!(
+                f,
+                "sample {} extends {} bytes past end of file",
+                sample + 1,
+                missing
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Longest sample a module header can describe, in bytes.
+const MAX_SAMPLE_BYTES: usize = 2 * 0xFFFF;
+
+pub struct Sample {
+    pub name: String,
+    /// Length in words, always half the length of `data`
+    pub(crate) length: usize,
+    pub finetune: i8,
+    pub volume: u8,
+    /// Loop start and length in words. Loops reaching past the end of the
+    /// sample are cut off when playing.
+    pub loop_start: usize,
+    pub loop_len: usize,
+    pub(crate) data: Vec<i8>,
+}
+
+impl Sample {
+    /// A sample at full volume without a loop, holding `data`.
+    pub fn new(name: &str, data: Vec<i8>) -> Sample {
+        let mut sample = Sample {
+            name: name.to_string(),
+            length: 0,
+            finetune: 0,
+            volume: 64,
+            loop_start: 0,
+            loop_len: 1,
+            data: Vec::new(),
+        };
+        sample.set_data(data);
+        sample
+    }
+
+    /// Length in words.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn data(&self) -> &[i8] {
+        &self.data
+    }
+
+    /// Replaces the PCM data. Modules store samples in whole words of at
+    /// most 128 KiB, so an odd last byte or anything beyond that is dropped.
+    pub fn set_data(&mut self, mut data: Vec<i8>) {
+        data.truncate(cmp::min(data.len(), MAX_SAMPLE_BYTES) & !1);
+        self.length = data.len() / 2;
+        self.data = data;
+    }
+
+    pub fn load(sample_data: &[u8]) -> Sample {
+        let mut name_vec = Vec::new();
+        name_vec.extend_from_slice(&sample_data[0..22]);
+        let name = String::from_utf8_lossy(&name_vec).to_string();
+        let length_slice = &sample_data[22..24];
+        let length = u16::from_be_bytes(length_slice.try_into().unwrap()) as usize;
+        let mut finetune = sample_data[24] as i8;
+        finetune &= 0x0F;
+        if finetune >= 8 {
+            finetune -= 16;
+        }
+        let volume = sample_data[25];
+        // println!("VOL: {}", volume);
+
+        let loop_start_slice = &sample_data[26..28];
+        let loop_start = u16::from_be_bytes(loop_start_slice.try_into().unwrap()) as usize;
+        let loop_len_slice = &sample_data[28..30];
+        let loop_len = u16::from_be_bytes(loop_len_slice.try_into().unwrap()) as usize;
+        let data: Vec<i8> = Vec::new();
+        Sample {
+            name,
+            length,
+            finetune,
+            volume,
+            loop_start,
+            loop_len,
+            data,
+        }
+    }
+    /// Encodes the sample header the way [`Sample::load`] reads it, with the
+    /// length of the sample's data.
+    pub fn save(&self) -> [u8; 30] {
+        let mut header = [0; 30];
+        let name = self.name.as_bytes();
+        let name_length = cmp::min(name.len(), 22);
+        header[0..name_length].copy_from_slice(&name[..name_length]);
+        header[22..24].copy_from_slice(&((self.data.len() / 2) as u16).to_be_bytes());
+        header[24] = self.finetune as u8 & 0x0F;
+        header[25] = self.volume;
+        header[26..28].copy_from_slice(&(self.loop_start as u16).to_be_bytes());
+        header[28..30].copy_from_slice(&(self.loop_len as u16).to_be_bytes());
+        header
+    }
+
+    /// Copies the sample's PCM data from `pcm`, zero-padding it to the length
+    /// given in the header if `pcm` is shorter. Returns the number of bytes
+    /// the sample occupies in the file.
+    pub fn load_data(&mut self, pcm: &[u8]) -> usize {
+        // println!("LOAD SAMPLE: {}", self.length * 2);
+        if self.length == 0 {
+            return 0;
+        }
+        let byte_length = self.length * 2;
+        let available = cmp::min(byte_length, pcm.len());
+        self.data
+            .extend(pcm[..available].iter().map(|&byte| byte as i8));
+        self.data.resize(byte_length, 0);
+        byte_length
+    }
+}
+
+const NOTE_NAMES: [&str; 12] = [
+    "C-", "C#", "D-", "D#", "E-", "F-", "F#", "G-", "G#", "A-", "A#", "B-",
+];
+
+/// A note of the ProTracker period table, from C-0 to B-4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note(usize);
+
+impl Note {
+    /// The note at `index` of the period table, from 1 for C-0 to 60 for
+    /// B-4.
+    pub fn new(index: usize) -> Option<Note> {
+        if (1..BASE_P_TABLE.len()).contains(&index) {
+            Some(Note(index))
+        } else {
+            None
+        }
+    }
+
+    /// Position in the period table, starting at 1 for C-0.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+
+    /// Period of the note without finetune.
+    pub fn period(&self) -> u16 {
+        BASE_P_TABLE[self.0] as u16
+    }
+
+    pub fn octave(&self) -> usize {
+        (self.0 - 1) / 12
+    }
+}
+
+impl fmt::Display for Note {
+    /// Formats the note the way ProTracker displays it, e.g. `C-2`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", NOTE_NAMES[(self.0 - 1) % 12], self.octave())
+    }
+}
+
+/// One cell of a pattern: what happens on one channel in one row.
+#[derive(Debug, Clone, Copy)]
+pub struct Event {
+    pub(crate) sample: usize,
+    pub(crate) note: usize,
+    pub(crate) period: usize,
+    pub(crate) fx: usize,
+    pub(crate) fx_param: usize,
+}
+
+impl Event {
+    /// A cell playing `note` with sample number `sample` (1 to 31) and
+    /// effect `effect` (0x0 to 0xF). Returns `None` for numbers a module
+    /// cannot store.
+    pub fn new(note: Option<Note>, sample: Option<usize>, effect: u8, param: u8) -> Option<Event> {
+        let sample = sample.unwrap_or(0);
+        if sample > 31 || effect > 0xF {
+            return None;
+        }
+        let note = note.map_or(0, |note| note.0);
+        Some(Event {
+            sample,
+            note,
+            period: BASE_P_TABLE[note] as usize,
+            fx: effect as usize,
+            fx_param: param as usize,
+        })
+    }
+
+    /// The note triggered, snapped to the nearest entry of the period table.
+    pub fn note(&self) -> Option<Note> {
+        if self.note > 0 {
+            Some(Note(self.note))
+        } else {
+            None
+        }
+    }
+
+    /// The period stored in the file, if it is not exactly the period of
+    /// [`Event::note`].
+    pub fn raw_period(&self) -> Option<u16> {
+        if self.period > 0 && self.period != BASE_P_TABLE[self.note] as usize {
+            Some(self.period as u16)
+        } else {
+            None
+        }
+    }
+
+    /// Sample number, starting at 1.
+    pub fn sample(&self) -> Option<usize> {
+        if self.sample > 0 {
+            Some(self.sample)
+        } else {
+            None
+        }
+    }
+
+    /// Effect command, 0x0 to 0xF.
+    pub fn effect(&self) -> u8 {
+        self.fx as u8
+    }
+
+    pub fn effect_param(&self) -> u8 {
+        self.fx_param as u8
+    }
+}
+
+pub struct Row {
+    pub(crate) events: Vec<Event>,
+}
+
+impl Row {
+    /// The row's events, one per channel.
+    pub fn cells(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    pub fn cell(&self, channel_index: usize) -> Option<&Event> {
+        self.events.get(channel_index)
+    }
+}
+
+pub struct Pattern {
+    pub(crate) rows: Vec<Row>,
+}
+
+impl Pattern {
+    pub fn rows(&self) -> impl Iterator<Item = &Row> {
+        self.rows.iter()
+    }
+
+    pub fn row(&self, row_index: usize) -> Option<&Row> {
+        self.rows.get(row_index)
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub(crate) fn load(pattern_data: &[u8], channel_count: usize) -> Pattern {
+        let mut pattern = Pattern { rows: Vec::new() };
+
+        for row_index in 0..64 {
+            let mut row = Row { events: Vec::new() };
+            for channel_index in 0..channel_count {
+                let offset = channel_index * 4 + (row_index * channel_count * 4);
+                let sample = (pattern_data[offset] & 0xF0 | pattern_data[offset + 2] >> 4) as usize;
+                let fx = (pattern_data[offset + 2] & 0x0F) as usize;
+                let fx_param = (pattern_data[offset + 3]) as usize;
+                let mut note = 0;
+
+                let period = ((((pattern_data[offset] & 0x0F) as i16) << 8)
+                    | pattern_data[offset + 1] as i16) as isize;
+                let mut bestd = (period - BASE_P_TABLE[0]).abs();
+                if period > 0 {
+                    for (index, table_period) in BASE_P_TABLE.iter().enumerate().skip(1) {
+                        let d = (period - table_period).abs();
+                        if d < bestd {
+                            bestd = d;
+                            note = index;
+                        }
+                    }
+                }
+                row.events.push(Event {
+                    sample,
+                    fx,
+                    fx_param,
+                    period: period as usize,
+                    note,
+                })
+            }
+            pattern.rows.push(row);
+        }
+
+        pattern
+    }
+
+    /// Encodes the pattern the way [`Pattern::load`] reads it, turning notes
+    /// back into periods. Periods that did not match a note are kept.
+    fn save(&self, out: &mut Vec<u8>) {
+        for row in self.rows.iter() {
+            for event in row.events.iter() {
+                let period = match event.raw_period() {
+                    Some(period) => period as usize,
+                    None => BASE_P_TABLE[event.note] as usize,
+                };
+                out.push((event.sample & 0xF0) as u8 | (period >> 8) as u8);
+                out.push(period as u8);
+                out.push(((event.sample & 0x0F) << 4) as u8 | event.fx as u8);
+                out.push(event.fx_param as u8);
+            }
+        }
+    }
+
+    /// Joins two patterns side by side, the way Startrekker stores its
+    /// 8 channel patterns as pairs of 4 channel ones.
+    fn join(mut self, right: Pattern) -> Pattern {
+        for (row, right_row) in self.rows.iter_mut().zip(right.rows) {
+            row.events.extend(right_row.events);
+        }
+        self
+    }
+}
+
+/// Number of channels announced by the tag at offset 1080, or `None` if the
+/// tag is unknown and the file is assumed to be a 15 sample module.
+fn channel_count_from_tag(tag: &[u8]) -> Option<usize> {
+    match tag {
+        b"M.K." | b"M!K!" | b"M&K!" | b"4TLF" | b"N.T." | b"FLT4" => Some(4),
+        b"FLT8" | b"CD81" | b"OKTA" | b"OCTA" => Some(8),
+        [count @ b'1'..=b'9', b'C', b'H', b'N'] => Some((count - b'0') as usize),
+        [tens @ b'1'..=b'3', ones @ b'0'..=b'9', b'C', b'H' | b'N'] => {
+            let count = ((tens - b'0') * 10 + (ones - b'0')) as usize;
+            if (10..=32).contains(&count) {
+                Some(count)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Tag announcing `channel_count` channels, preferring the ones ProTracker
+/// and FastTracker write. ProTracker only takes `M.K.` up to 64 patterns.
+fn tag_for_channel_count(channel_count: usize, pattern_count: usize) -> [u8; 4] {
+    match channel_count {
+        4 if pattern_count > 64 => *b"M!K!",
+        4 => *b"M.K.",
+        1..=9 => [b'0' + channel_count as u8, b'C', b'H', b'N'],
+        _ => [
+            b'0' + (channel_count / 10) as u8,
+            b'0' + (channel_count % 10) as u8,
+            b'C',
+            b'H',
+        ],
+    }
+}
+
+/// Checks the header of an untagged file for values Ultimate Soundtracker and
+/// its successors could have written, since 15 sample modules carry no tag
+/// that would tell them apart from any other file.
+fn is_soundtracker_header(module: &[u8]) -> bool {
+    let mut sample_bytes = 0;
+    for sample_header in module[20..470].chunks(30) {
+        let name = &sample_header[0..22];
+        if name.iter().any(|&c| c != 0 && !(32..127).contains(&c)) {
+            return false;
+        }
+        let length = u16::from_be_bytes([sample_header[22], sample_header[23]]) as usize;
+        // no finetune yet, and volumes are still within the Amiga's range
+        if sample_header[24] != 0 || sample_header[25] > 64 || length > 32768 {
+            return false;
+        }
+        sample_bytes += length * 2;
+    }
+    let song_length = module[470] as usize;
+    if song_length == 0 || song_length > 128 || module[471] >= 240 {
+        return false;
+    }
+    let orders = &module[472..600];
+    if orders.iter().any(|&pattern| pattern >= 64) {
+        return false;
+    }
+    // all patterns have to be present, and sample data has to follow them
+    let pattern_end = 600 + (*orders.iter().max().unwrap_or(&0) as usize + 1) * 1024;
+    module.len() >= pattern_end && (sample_bytes == 0 || module.len() > pattern_end)
+}
+
+/// Whether the loop starts of a 15 sample module are in bytes, which is
+/// certain once a loop only fits into its sample that way.
+fn loop_starts_in_bytes(samples: &[Sample]) -> bool {
+    samples.iter().any(|sample| {
+        sample.loop_len > 1
+            && sample.loop_start + sample.loop_len > sample.length
+            && sample.loop_start / 2 + sample.loop_len <= sample.length
+    })
+}
+
+pub(crate) const BASE_P_TABLE: [isize; 61] = [
+    0, 1712, 1616, 1525, 1440, 1357, 1281, 1209, 1141, 1077, 1017, 961, 907, 856, 808, 762, 720,
+    678, 640, 604, 570, 538, 508, 480, 453, 428, 404, 381, 360, 339, 320, 302, 285, 269, 254, 240,
+    226, 214, 202, 190, 180, 170, 160, 151, 143, 135, 127, 120, 113, 107, 101, 95, 90, 85, 80, 76,
+    71, 67, 64, 60, 57,
+];
+
+/// The song itself: everything read from a module file, independent of any
+/// playback state. A [`Player`](crate::Player) shares it through an `Arc`,
+/// so several players or an editor view can use the same song.
+///
+/// Patterns, the order list and sample data are changed through methods
+/// that keep them consistent, so that any module plays without panicking.
+pub struct Module {
+    pub name: String,
+    pub format: ModuleFormat,
+    pub(crate) channel_count: usize,
+    pub(crate) samples: Vec<Sample>,
+    pub(crate) patterns: Vec<Pattern>,
+    /// Pattern played at each position. Always holds 128 entries, of which
+    /// the first `position_count` make up the song.
+    pub(crate) pattern_list: Vec<usize>,
+    pub(crate) position_count: usize,
+    /// Tag the module was loaded with, written back by [`Module::save`]
+    tag: Option<[u8; 4]>,
+    /// Restart position, or the tempo for Soundtracker modules.
+    pub restart: usize,
+    /// Quirks to play the module with, detected on load.
+    pub compatibility: CompatibilityProfile,
+}
+
+impl Module {
+    /// Loads a module, failing if any part of it is missing from the file.
+    pub fn load(module: &[u8]) -> Result<Module, LoadError> {
+        Module::parse(module, false)
+    }
+
+    /// Loads a module like [`Module::load`], but zero-pads samples whose
+    /// data is cut off by the end of the file, like classic players do.
+    pub fn load_lenient(module: &[u8]) -> Result<Module, LoadError> {
+        Module::parse(module, true)
+    }
+
+    fn parse(module: &[u8], lenient: bool) -> Result<Module, LoadError> {
+        // the smallest possible module is a 15 sample module without patterns
+        if module.len() < 600 {
+            return Err(LoadError::TooShort {
+                length: module.len(),
+                required: 600,
+            });
+        }
+
+        let mut large = false;
+        let mut format = ModuleFormat::Soundtracker;
+        let mut channel_count = 4;
+        let mut startrekker_8 = false;
+        let mut tag: &[u8] = &[];
+
+        let mut name_vec = Vec::new();
+        name_vec.extend_from_slice(&module[0..20]);
+        let name = String::from_utf8_lossy(&name_vec);
+
+        if module.len() >= 1084 {
+            // println!("Tag: {}", tag);
+            if let Some(count) = channel_count_from_tag(&module[1080..1084]) {
+                tag = &module[1080..1084];
+                large = true;
+                format = ModuleFormat::ProTracker;
+                channel_count = count;
+                startrekker_8 = tag == b"FLT8";
+            }
+        }
+        if !large && !is_soundtracker_header(module) {
+            return Err(LoadError::BadHeader("unknown module format"));
+        }
+
+        let mut offset = 20;
+
+        let mut sample_count = 15;
+        if large {
+            sample_count = 31;
+        };
+        let mut samples: Vec<Sample> = Vec::new();
+        for _sample_index in 0..sample_count {
+            samples.push(Sample::load(&module[offset..(offset + 30)]));
+            offset += 30;
+        }
+
+        let position_count = module[offset] as usize;
+        if position_count == 0 || position_count > 128 {
+            return Err(LoadError::BadHeader(
+                "song length must be between 1 and 128",
+            ));
+        }
+
+        let tempo = module[offset + 1] as usize;
+
+        offset += 2;
+
+        let mut pattern_count: usize = 1;
+        let mut pattern_list: Vec<usize> = Vec::new();
+        for pos_index in 0..128 {
+            pattern_count = cmp::max(pattern_count, module[offset + pos_index] as usize + 1);
+            pattern_list.push(module[offset + pos_index] as usize);
+        }
+        if pattern_count > 128 {
+            return Err(LoadError::BadHeader(
+                "pattern number in order list out of range",
+            ));
+        }
+
+        offset += 128;
+
+        if large {
+            offset += 4
+        }
+
+        // Startrekker's FLT8 stores every pattern as two 4 channel halves
+        // and only uses even pattern numbers in the order list
+        let stored_channel_count = if startrekker_8 { 4 } else { channel_count };
+        if startrekker_8 {
+            pattern_count += pattern_count % 2;
+        }
+        let pattern_size = 4 * stored_channel_count * 64;
+
+        let mut patterns: Vec<Pattern> = Vec::new();
+
+        for pattern_index in 0..pattern_count {
+            let data_start = offset + pattern_index * pattern_size;
+            let data_end = data_start + pattern_size;
+            if data_end > module.len() {
+                return Err(LoadError::PatternDataPastEof {
+                    pattern: pattern_index,
+                });
+            }
+            patterns.push(Pattern::load(
+                &module[data_start..data_end],
+                stored_channel_count,
+            ));
+        }
+        // println!("OFFSET {} - {}", offset, pattern_count);
+
+        offset += pattern_count * pattern_size;
+
+        if startrekker_8 {
+            let mut halves = patterns.into_iter();
+            patterns = Vec::new();
+            while let (Some(left), Some(right)) = (halves.next(), halves.next()) {
+                patterns.push(left.join(right));
+            }
+            for pattern_index in pattern_list.iter_mut() {
+                *pattern_index /= 2;
+            }
+        }
+
+        // Ultimate Soundtracker gives loop starts in bytes instead of words,
+        // which only shows where a loop would otherwise end past its sample
+        if format.is_soundtracker() && loop_starts_in_bytes(&samples) {
+            for sample in samples.iter_mut() {
+                sample.loop_start /= 2;
+            }
+            let ultimate = patterns
+                .iter()
+                .flat_map(|pattern| pattern.rows.iter())
+                .flat_map(|row| row.events.iter())
+                .all(|event| matches!(event.fx, 1 | 2) || event.fx_param == 0);
+            if ultimate {
+                format = ModuleFormat::UltimateSoundtracker;
+                for event in patterns
+                    .iter_mut()
+                    .flat_map(|pattern| pattern.rows.iter_mut())
+                    .flat_map(|row| row.events.iter_mut())
+                {
+                    match event.fx {
+                        1 => event.fx = 0,
+                        // the low nibble bends down, the high nibble up
+                        2 if event.fx_param & 0x0F == 0 => {
+                            event.fx = 1;
+                            event.fx_param >>= 4;
+                        }
+                        2 => event.fx_param &= 0x0F,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        for (sample_index, sample) in samples.iter_mut().enumerate() {
+            let length = sample.length;
+            // println!(
+            //     "load PCM l: {} ls: {} ll: {}",
+            //     length, sample.loop_start, sample.loop_len
+            // );
+            let start = cmp::min(offset, module.len());
+            let end = cmp::min(offset + length * 2, module.len());
+            if end - start < length * 2 && !lenient {
+                return Err(LoadError::SampleDataPastEof {
+                    sample: sample_index,
+                    missing: length * 2 - (end - start),
+                });
+            }
+            offset += sample.load_data(&module[start..end]);
+        }
+        let compatibility = CompatibilityProfile::detect(format, tag);
+        Ok(Module {
+            name: name.to_string(),
+            format,
+            channel_count,
+            samples,
+            patterns,
+            pattern_list,
+            position_count,
+            tag: tag.try_into().ok(),
+            restart: tempo,
+            compatibility,
+        })
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channel_count
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// Sample `index`, counting from 0, for editing in place.
+    pub fn sample_mut(&mut self, index: usize) -> Option<&mut Sample> {
+        self.samples.get_mut(index)
+    }
+
+    /// Replaces sample `index`, counting from 0. A sample read with
+    /// [`Sample::load`] but no data gets silence of the length in its
+    /// header. Returns `false` if the module has no such sample.
+    pub fn set_sample(&mut self, index: usize, mut sample: Sample) -> bool {
+        match self.samples.get_mut(index) {
+            Some(old) => {
+                sample.data.resize(sample.length * 2, 0);
+                *old = sample;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    /// Appends an empty pattern and returns its number, or `None` if the
+    /// module already has the 128 patterns an order list can refer to.
+    pub fn add_pattern(&mut self) -> Option<usize> {
+        if self.patterns.len() >= 128 {
+            return None;
+        }
+        let empty = Event::new(None, None, 0, 0).unwrap();
+        self.patterns.push(Pattern {
+            rows: (0..64)
+                .map(|_| Row {
+                    events: vec![empty; self.channel_count],
+                })
+                .collect(),
+        });
+        Some(self.patterns.len() - 1)
+    }
+
+    /// Puts `event` into `pattern` at `row` and `channel`. Returns `false`
+    /// if there is no such cell or `event` plays a sample the module lacks.
+    pub fn set_cell(&mut self, pattern: usize, row: usize, channel: usize, event: Event) -> bool {
+        if event.sample > self.samples.len() {
+            return false;
+        }
+        let cell = self
+            .patterns
+            .get_mut(pattern)
+            .and_then(|pattern| pattern.rows.get_mut(row))
+            .and_then(|row| row.events.get_mut(channel));
+        match cell {
+            Some(cell) => {
+                *cell = event;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pattern played at each of the 128 positions, of which the first
+    /// [`Module::position_count`] make up the song.
+    pub fn pattern_list(&self) -> &[usize] {
+        &self.pattern_list
+    }
+
+    /// Plays `pattern` at `position`. Returns `false` if the position is
+    /// past the 128 an order list holds or the pattern does not exist.
+    pub fn set_order(&mut self, position: usize, pattern: usize) -> bool {
+        if position >= self.pattern_list.len() || pattern >= self.patterns.len() {
+            return false;
+        }
+        self.pattern_list[position] = pattern;
+        true
+    }
+
+    /// Number of positions in the song.
+    pub fn position_count(&self) -> usize {
+        self.position_count
+    }
+
+    /// Sets the song length. Returns `false` unless `count` is between 1
+    /// and 128.
+    pub fn set_position_count(&mut self, count: usize) -> bool {
+        if !(1..=self.pattern_list.len()).contains(&count) {
+            return false;
+        }
+        self.position_count = count;
+        true
+    }
+
+    /// The patterns making up the song, in playing order, together with
+    /// their pattern numbers.
+    pub fn orders(&self) -> impl Iterator<Item = (usize, &Pattern)> {
+        self.pattern_list[..self.position_count]
+            .iter()
+            .map(|&pattern_index| (pattern_index, &self.patterns[pattern_index]))
+    }
+
+    /// Tempo in BPM the song starts with. Soundtracker keeps it in the byte
+    /// ProTracker uses for the restart position, with 0x78 meaning the
+    /// default tempo.
+    pub fn initial_bpm(&self) -> usize {
+        if self.format.is_soundtracker()
+            && self.restart != 0
+            && self.restart != 0x78
+            && self.restart < 240
+        {
+            709379 * 125 / 50 / ((240 - self.restart) * 122)
+        } else {
+            125
+        }
+    }
+
+    /// Encodes the song as a 31 sample module, keeping the tag it was loaded
+    /// with if that still fits. Modules loaded from 31 sample files other
+    /// than Startrekker's `FLT8` are written back byte for byte, apart from
+    /// anything trailing the last sample. Patterns numbered above the
+    /// highest one in the order list are left out, since loaders take the
+    /// number of patterns from the order list.
+    ///
+    /// A Soundtracker tempo has no place in the header, so it is written as
+    /// an `Fxx` in the first free channel of the first row played. If every
+    /// channel of that row has an effect, the tempo is lost.
+    pub fn save(&self) -> Vec<u8> {
+        let mut module = Vec::new();
+        let name = self.name.as_bytes();
+        let name_length = cmp::min(name.len(), 20);
+        module.extend_from_slice(&name[..name_length]);
+        module.resize(20, 0);
+
+        for sample_index in 0..31 {
+            match self.samples.get(sample_index) {
+                Some(sample) => module.extend_from_slice(&sample.save()),
+                None => module.extend_from_slice(&[0; 30]),
+            }
+        }
+
+        module.push(self.position_count as u8);
+        if self.format.is_soundtracker() {
+            module.push(0x7f);
+        } else {
+            module.push(self.restart as u8);
+        }
+        module.extend(self.pattern_list.iter().map(|&pattern| pattern as u8));
+        module.extend_from_slice(&self.save_tag());
+
+        for pattern in self.saved_patterns() {
+            pattern.save(&mut module);
+        }
+        if self.format.is_soundtracker() && self.initial_bpm() != 125 {
+            // up to F20 sets the speed instead
+            let bpm = self.initial_bpm().clamp(33, 255) as u8;
+            let row_length = self.channel_count * 4;
+            let row = 1084 + self.pattern_list[0] * 64 * row_length;
+            let free = module[row..row + row_length]
+                .chunks_exact_mut(4)
+                .find(|cell| cell[2] & 0x0f == 0 && cell[3] == 0);
+            if let Some(cell) = free {
+                cell[2] |= 0x0f;
+                cell[3] = bpm;
+            }
+        }
+
+        for sample in self.samples.iter() {
+            module.extend(sample.data.iter().map(|&byte| byte as u8));
+        }
+        module
+    }
+
+    /// Patterns up to the highest one the order list refers to, which is as
+    /// many as a loader reads back.
+    fn saved_patterns(&self) -> &[Pattern] {
+        let last = self.pattern_list.iter().copied().max().unwrap_or(0);
+        &self.patterns[..=last]
+    }
+
+    /// The loaded tag, unless the channel count or number of patterns has
+    /// outgrown it. `FLT8` is never kept since [`Module::save`] does not
+    /// split patterns into halves.
+    fn save_tag(&self) -> [u8; 4] {
+        match self.tag {
+            Some(tag)
+                if &tag != b"FLT8"
+                    && channel_count_from_tag(&tag) == Some(self.channel_count)
+                    && (self.saved_patterns().len() <= 64 || &tag != b"M.K.") =>
+            {
+                tag
+            }
+            _ => tag_for_channel_count(self.channel_count, self.saved_patterns().len()),
+        }
+    }
+}
@@ -0,0 +1,1529 @@
+use crate::blep::Blep;
+use crate::config::{ChannelLayout, Interpolation, LoopMode, PlayerConfig};
+use crate::filter::AmigaFilter;
+use crate::module::{Event, LoadError, Module, Sample, BASE_P_TABLE};
+use std::cmp;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+const OUTFPS: usize = 50; // approx. pal timing
+const MAX_SONG_DURATION: u64 = 4 * 60 * 60; // seconds
+const SINC_TAPS: usize = 8;
+/// Speeds of `EFx`, added to a counter each tick that inverts the next loop
+/// byte whenever it reaches 128
+const FUNK_TABLE: [usize; 16] = [0, 5, 6, 7, 8, 10, 11, 13, 16, 19, 22, 26, 32, 43, 64, 128];
+const SINC_PHASES: usize = 1024;
+/// Time a full volume change takes when declicking
+const RAMP_SECONDS: f32 = 0.004;
+
+/// How long a song plays, see [`Player::duration`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SongDuration {
+    /// Time until the song ends or starts over.
+    pub total: Duration,
+    /// Time at which each position is first reached, `None` for positions
+    /// the song skips.
+    pub positions: Vec<Option<Duration>>,
+}
+
+/// Lanczos windowed sinc weights for `SINC_PHASES + 1` fractional positions,
+/// `SINC_TAPS` each and normalised to unity gain.
+fn sinc_table() -> &'static [f32] {
+    static TABLE: OnceLock<Vec<f32>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let half = (SINC_TAPS / 2) as f64;
+        let sinc = |x: f64| {
+            if x == 0.0 {
+                1.0
+            } else {
+                let x = x * std::f64::consts::PI;
+                x.sin() / x
+            }
+        };
+        let mut table = Vec::with_capacity((SINC_PHASES + 1) * SINC_TAPS);
+        for phase in 0..=SINC_PHASES {
+            let fac = phase as f64 / SINC_PHASES as f64;
+            let weights: Vec<f64> = (0..SINC_TAPS)
+                .map(|tap| {
+                    let x = tap as f64 + 1.0 - half - fac;
+                    sinc(x) * sinc(x / half)
+                })
+                .collect();
+            let sum: f64 = weights.iter().sum();
+            table.extend(weights.iter().map(|weight| (weight / sum) as f32));
+        }
+        table
+    })
+}
+
+/// Bytes of sample `index`, or the copy `EFx` has modified.
+fn sample_bytes<'a>(
+    samples: &'a [Sample],
+    overrides: &'a [Option<Vec<i8>>],
+    index: usize,
+) -> &'a [i8] {
+    overrides[index].as_deref().unwrap_or(&samples[index].data)
+}
+
+/// The loop Paula's location and length registers point to, which DMA
+/// moves on to whenever it reaches the end of the block it plays.
+#[derive(Clone, Copy)]
+struct Repeat {
+    sample: usize,
+    /// End of the loop in bytes
+    end: usize,
+    length: usize,
+    /// The first two bytes of a one-shot sample, which ProTracker clears
+    silent: bool,
+}
+
+impl Repeat {
+    fn of(index: usize, sample: &Sample) -> Repeat {
+        // loops reaching past the sample data are cut off at its end
+        let loop_end = cmp::min(sample.loop_start + sample.loop_len, sample.length);
+        if sample.loop_len > 2 && loop_end > sample.loop_start + 1 {
+            Repeat {
+                sample: index,
+                end: 2 * loop_end,
+                length: 2 * (loop_end - sample.loop_start),
+                silent: false,
+            }
+        } else {
+            Repeat {
+                sample: index,
+                end: 2,
+                length: 2,
+                silent: true,
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Voice {
+    pos: f32,
+    pub sample: Option<usize>,
+    pub period: isize,
+    pub volume: isize,
+    /// End of the block DMA plays, which loops over its last `loop_length`
+    /// bytes unless another loop was latched
+    sample_length: usize,
+    loop_length: usize,
+    /// Whether playback wrapped around the loop since the last trigger
+    looped: bool,
+    /// Playing the silent loop of a one-shot sample
+    silent: bool,
+    /// Loop latched for when the current block ends
+    next: Option<Repeat>,
+    /// Volume applied to the output, which follows `volume` gradually when
+    /// declicking
+    gain: f32,
+    /// Output level Paula currently holds, for [`Interpolation::Blep`]
+    level: f32,
+    blep: Blep,
+}
+
+impl Voice {
+    fn new() -> Voice {
+        Voice {
+            pos: 0.0,
+            period: 65535,
+            volume: 0,
+            sample: None,
+            sample_length: 0,
+            loop_length: 1,
+            looped: false,
+            silent: false,
+            next: None,
+            gain: 0.0,
+            level: 0.0,
+            blep: Blep::new(),
+        }
+    }
+
+    /// Renders the voice into `buffer` as mono samples, advancing by
+    /// `clock_ratio / period` sample bytes per output sample. `samples` looks
+    /// up the bytes of a sample by index. The volume moves by at most `ramp`
+    /// per output sample, or at once when `ramp` is 0.
+    fn render<'a>(
+        &mut self,
+        samples: impl Fn(usize) -> &'a [i8],
+        buffer: &mut [f32],
+        clock_ratio: f32,
+        interpolation: Interpolation,
+        ramp: f32,
+    ) {
+        let step = clock_ratio / self.period as f32;
+        if !step.is_finite() {
+            // without a period Paula would never fetch a byte
+            buffer.fill(0.0);
+            return;
+        }
+        if interpolation == Interpolation::Blep {
+            self.render_blep(samples, buffer, clock_ratio, ramp);
+            return;
+        }
+        let Some(mut index) = self.sample else {
+            return;
+        };
+        let mut data = samples(index);
+        for out in buffer.iter_mut() {
+            self.pos += step;
+            self.wrap();
+            if self.sample != Some(index) {
+                index = self.sample.unwrap_or(index);
+                data = samples(index);
+            }
+            let int_pos = self.pos.floor() as isize;
+            let fac = self.pos - self.pos.floor();
+
+            let sample_value = match interpolation {
+                Interpolation::Nearest => self.sample_at(data, int_pos),
+                Interpolation::Linear => {
+                    self.sample_at(data, int_pos) * (1.0 - fac)
+                        + self.sample_at(data, int_pos + 1) * fac
+                }
+                Interpolation::Hermite => {
+                    let p0 = self.sample_at(data, int_pos - 1);
+                    let p1 = self.sample_at(data, int_pos);
+                    let p2 = self.sample_at(data, int_pos + 1);
+                    let p3 = self.sample_at(data, int_pos + 2);
+                    let c1 = 0.5 * (p2 - p0);
+                    let c2 = p0 - 2.5 * p1 + 2.0 * p2 - 0.5 * p3;
+                    let c3 = 0.5 * (p3 - p0) + 1.5 * (p1 - p2);
+                    ((c3 * fac + c2) * fac + c1) * fac + p1
+                }
+                Interpolation::Sinc => {
+                    let phase = (fac * SINC_PHASES as f32) as usize;
+                    let weights = &sinc_table()[phase * SINC_TAPS..(phase + 1) * SINC_TAPS];
+                    let first = int_pos + 1 - (SINC_TAPS / 2) as isize;
+                    weights
+                        .iter()
+                        .enumerate()
+                        .map(|(tap, weight)| self.sample_at(data, first + tap as isize) * weight)
+                        .sum()
+                }
+                Interpolation::Blep => unreachable!(),
+            };
+
+            *out = sample_value / 128.0 * self.step_gain(ramp);
+        }
+    }
+
+    /// Renders Paula's zero-order hold output, starting every sample byte
+    /// with a band-limited step at the exact time Paula fetches it.
+    fn render_blep<'a>(
+        &mut self,
+        samples: impl Fn(usize) -> &'a [i8],
+        buffer: &mut [f32],
+        clock_ratio: f32,
+        ramp: f32,
+    ) {
+        let Some(mut index) = self.sample else {
+            return;
+        };
+        let mut data = samples(index);
+        let step = clock_ratio / self.period as f32;
+        if !step.is_finite() {
+            buffer.fill(0.0);
+            return;
+        }
+        for out in buffer.iter_mut() {
+            let volume = self.step_gain(ramp) / 128.0;
+            // triggers and volume changes take effect right away
+            let held = self.sample_at(data, self.pos.floor() as isize) * volume;
+            if held != self.level {
+                self.blep.add(0.0, held - self.level);
+                self.level = held;
+            }
+
+            let start = self.pos;
+            self.pos += step;
+            let mut fetch = start.floor() + 1.0;
+            while fetch <= self.pos {
+                let level = self.sample_at(data, fetch as isize) * volume;
+                if level != self.level {
+                    self.blep.add((self.pos - fetch) / step, level - self.level);
+                    self.level = level;
+                }
+                fetch += 1.0;
+            }
+            self.wrap();
+            if self.sample != Some(index) {
+                index = self.sample.unwrap_or(index);
+                data = samples(index);
+            }
+
+            *out = self.level + self.blep.next();
+        }
+    }
+
+    /// Moves `gain` towards the volume, by at most `ramp` or at once when
+    /// `ramp` is 0, and returns it.
+    fn step_gain(&mut self, ramp: f32) -> f32 {
+        let target = self.volume as f32 / 64.0;
+        self.gain = if ramp > 0.0 {
+            self.gain + (target - self.gain).clamp(-ramp, ramp)
+        } else {
+            target
+        };
+        self.gain
+    }
+
+    /// Moves on once the voice has passed the end of the block DMA plays:
+    /// to the latched loop if there is one, or back to the start of the
+    /// current loop.
+    fn wrap(&mut self) {
+        if !self.pos.is_finite() || self.loop_length == 0 {
+            self.sample = None;
+            return;
+        }
+        while self.pos >= self.sample_length as f32 {
+            match self.next.take() {
+                Some(repeat) => {
+                    let past_end = self.pos - self.sample_length as f32;
+                    self.sample = Some(repeat.sample);
+                    self.sample_length = repeat.end;
+                    self.loop_length = repeat.length;
+                    self.silent = repeat.silent;
+                    self.pos = (repeat.end - repeat.length) as f32 + past_end;
+                }
+                None => self.pos -= self.loop_length as f32,
+            }
+            self.looped = true;
+        }
+    }
+
+    /// Sample byte at `index` as the voice plays it: past the end the loop
+    /// repeats, and once the voice has looped, bytes before the loop start
+    /// come from the end of the loop. Before the start of the sample and in
+    /// silent loops there is silence. A loop latched from another sample is
+    /// only heard once the voice gets there.
+    fn sample_at(&self, data: &[i8], index: isize) -> f32 {
+        let end = self.sample_length as isize;
+        if self.silent || (index >= end && self.next.is_some_and(|next| next.silent)) {
+            return 0.0;
+        }
+        let loop_length = self.loop_length as isize;
+        let loop_start = end - loop_length;
+        let index = if index >= end || (self.looped && index < loop_start) {
+            loop_start + (index - loop_start).rem_euclid(loop_length)
+        } else {
+            index
+        };
+        if index < 0 {
+            return 0.0;
+        }
+        data[index as usize] as f32
+    }
+
+    /// Advances the voice like [`Voice::render`] would, without producing
+    /// any output.
+    fn skip(&mut self, samples: usize, clock_ratio: f32) {
+        if self.sample.is_none() {
+            return;
+        }
+        self.gain = self.volume as f32 / 64.0;
+        let step = clock_ratio / self.period as f32;
+        if !step.is_finite() {
+            return;
+        }
+        self.pos += samples as f32 * clock_ratio / self.period as f32;
+        if self.pos >= self.sample_length as f32 {
+            let past_end = self.pos - self.sample_length as f32;
+            self.pos = self.sample_length as f32;
+            self.wrap();
+            self.pos += past_end % self.loop_length as f32;
+        }
+    }
+
+    fn trigger(
+        &mut self,
+        sample_index: usize,
+        sample_length: usize,
+        loop_length: usize,
+        offset: isize,
+    ) {
+        // println!(
+        //     "Trig: {} {} {} {}",
+        //     sample_index, sample_length, loop_length, offset
+        // );
+        self.sample = Some(sample_index);
+        self.sample_length = sample_length;
+        self.loop_length = loop_length;
+        self.looped = false;
+        self.silent = false;
+        self.next = None;
+        self.pos = (offset as f32).min(sample_length as f32 - 1.0);
+    }
+
+    /// Starts the sample `repeat` belongs to, with DMA moving on to `repeat`
+    /// once the whole sample has played.
+    fn trigger_repeat(&mut self, repeat: Repeat, sample: &Sample, offset: isize) {
+        if !repeat.silent {
+            self.trigger(repeat.sample, repeat.end, repeat.length, offset);
+        } else if sample.length > 0 {
+            self.trigger(repeat.sample, sample.length * 2, 2, offset);
+            self.next = Some(repeat);
+        }
+    }
+
+    /// Writes the loop registers without restarting DMA: a playing voice
+    /// switches to `repeat` once its current block ends.
+    fn latch(&mut self, repeat: Repeat) {
+        if self.sample.is_some() {
+            self.next = Some(repeat);
+        }
+    }
+}
+
+fn clamp<T>(x: T, min: T, max: T) -> T
+where
+    T: Ord,
+{
+    cmp::max(min, cmp::min(max, x))
+}
+
+/// Default pan position of a channel, repeating the Amiga's LRRL layout.
+fn default_pan(channel_index: usize) -> f32 {
+    if matches!(channel_index % 4, 0 | 3) {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+/// Pan position for an `8xx` parameter: 00 is left, 80 centre, FF right.
+fn pan_from_effect(param: usize) -> f32 {
+    ((param as f32 - 128.0) / 127.0).clamp(-1.0, 1.0)
+}
+
+struct Channel {
+    note: usize,
+    period: usize,
+    sample: usize,
+    fine_tune: isize,
+    volume: usize,
+    loop_start: usize,
+    loop_count: usize,
+    retrig_count: usize,
+    vib_wave: usize,
+    vib_retr: usize,
+    vib_pos: usize,
+    vib_ampl: usize,
+    vib_speed: usize,
+    trem_wave: usize,
+    trem_retr: usize,
+    trem_pos: usize,
+    trem_ampl: usize,
+    trem_speed: usize,
+    fx_buf: [usize; 16],
+    fx_buf14: [usize; 16],
+    /// Pan set by `8xx` or `E8x` in extended panning mode
+    pan: Option<f32>,
+    /// `E31` rounds tone portamento to semitones
+    glissando: bool,
+    funk_speed: usize,
+    funk_offset: usize,
+    /// Loop byte `EFx` inverted last, relative to the loop start
+    funk_pos: usize,
+}
+
+impl Channel {
+    pub fn new() -> Channel {
+        Channel {
+            note: 0,
+            period: 0,
+            sample: 0,
+            fine_tune: 0,
+            volume: 0,
+            loop_start: 0,
+            loop_count: 0,
+            retrig_count: 0,
+            vib_wave: 0,
+            vib_retr: 0,
+            vib_pos: 0,
+            vib_ampl: 0,
+            vib_speed: 0,
+            trem_wave: 0,
+            trem_retr: 0,
+            trem_pos: 0,
+            trem_ampl: 0,
+            trem_speed: 0,
+            fx_buf: [0; 16],
+            fx_buf14: [0; 16],
+            pan: None,
+            glissando: false,
+            funk_speed: 0,
+            funk_offset: 0,
+            funk_pos: 0,
+        }
+    }
+    fn get_period(&mut self, p_table: &[Vec<i32>], mut offs: isize, fine_offs: isize) -> usize {
+        let mut ft: isize = self.fine_tune + fine_offs;
+        while ft > 7 {
+            offs += 1;
+            ft -= 16;
+        }
+        while ft < -8 {
+            offs -= 1;
+            ft += 16;
+        }
+        if self.note > 0 {
+            let clamped = clamp(self.note as isize + offs - 1, 0, 59) as usize;
+            return p_table[ft as usize & 0x0f][clamped] as usize;
+        }
+        0
+    }
+    /// The note's period in the finetune table at or above `period` in
+    /// pitch, like glissando plays it.
+    fn snap_to_note(&self, p_table: &[Vec<i32>], period: usize) -> usize {
+        let table = &p_table[self.fine_tune as usize & 0x0f];
+        table
+            .iter()
+            .map(|&entry| entry as usize)
+            .find(|&entry| entry <= period)
+            .unwrap_or(table[table.len() - 1] as usize)
+    }
+    fn set_period(&mut self, p_table: &[Vec<i32>], offs: isize, fine_offs: isize) {
+        if self.note > 0 {
+            self.period = self.get_period(p_table, offs, fine_offs);
+        }
+    }
+}
+
+/// Former name of [`Player`], kept for existing front ends.
+pub type ModPlayer = Player;
+
+/// Playback engine for a [`Module`]: the effect state machine, channel state
+/// and Paula voices.
+pub struct Player {
+    module: Arc<Module>,
+    config: PlayerConfig,
+    p_table: Vec<Vec<i32>>,
+    vib_table: Vec<Vec<Vec<i32>>>,
+    speed: usize,
+    bpm: usize,
+    tick_rate: usize,
+    tr_counter: usize,
+    cur_tick: usize,
+    cur_row: usize,
+    cur_pos: usize,
+    delay: usize,
+    position_jump: Option<usize>,
+    pattern_break: Option<usize>,
+    loop_jump: Option<usize>,
+    /// Rows played so far, one bit per row for every position
+    visited: Vec<u64>,
+    loops: usize,
+    /// `F00` stopped the song, whatever the loop mode
+    stopped: bool,
+    finished: bool,
+    channels: Vec<Channel>,
+    /// 0.0 plays every channel in the centre, 1.0 as far out as panned
+    stereo_separation: f32,
+    /// Whether `E00` switched the LED filter on
+    led_filter: bool,
+    filter: Option<AmigaFilter>,
+    /// Mixer settings per channel, kept when the song restarts or seeks
+    muted: Vec<bool>,
+    solo: Vec<bool>,
+    channel_gain: Vec<f32>,
+    /// -1.0 is left, 1.0 is right
+    pan: Vec<f32>,
+    /// Peak output of each voice during the last render call
+    levels: Vec<f32>,
+
+    voices: Vec<Voice>,
+    /// Notes fading out after a new one started on their channel, when
+    /// declicking
+    fades: Vec<Voice>,
+    voice_buf: Vec<f32>,
+    fade_buf: Vec<f32>,
+    /// Copies of samples `EFx` has modified, the module stays untouched
+    sample_overrides: Vec<Option<Vec<i8>>>,
+}
+
+impl Player {
+    /// Loads a module and creates a player for it, see [`Module::load`].
+    pub fn load(module: Vec<u8>) -> Result<Player, LoadError> {
+        Ok(Player::new(Arc::new(Module::load(&module)?)))
+    }
+
+    /// Loads a module leniently and creates a player for it, see
+    /// [`Module::load_lenient`].
+    pub fn load_lenient(module: Vec<u8>) -> Result<Player, LoadError> {
+        Ok(Player::new(Arc::new(Module::load_lenient(&module)?)))
+    }
+
+    /// Creates a player starting at the first position of `module`, with
+    /// the default output settings.
+    pub fn new(module: Arc<Module>) -> Player {
+        Player::with_config(module, PlayerConfig::default())
+    }
+
+    /// Creates a player starting at the first position of `module`.
+    pub fn with_config(module: Arc<Module>, config: PlayerConfig) -> Player {
+        // generate tables
+
+        let mut p_table: Vec<Vec<i32>> = Vec::new();
+
+        for ft in 0..16 {
+            let rft: i32 = -(if ft > 8 { ft - 16 } else { ft });
+            let fac: f32 = (2.0_f32).powf((rft as f32) / (12.0 * 16.0));
+            let mut inner: Vec<i32> = Vec::new();
+            // entry 0 of BASE_P_TABLE stands for "no note", the finetune
+            // tables start at C-0 so that note n plays entry n - 1
+            for base_period in BASE_P_TABLE.iter().skip(1) {
+                let entry = ((*base_period as f32) * fac) as i32;
+                inner.push(entry);
+            }
+            p_table.push(inner);
+        }
+
+        let mut vib_table = Vec::new();
+
+        let mut vib_0 = Vec::new();
+        let mut vib_1 = Vec::new();
+        let mut vib_2 = Vec::new();
+
+        for ampl in 0..15 {
+            let mut vib_0_inner = Vec::new();
+            let mut vib_1_inner = Vec::new();
+            let mut vib_2_inner = Vec::new();
+            let scale = (ampl as f32) + 1.5;
+            for x in 0..64 {
+                let vib_0_entry = (scale * ((x as f32) / 32.0).sin()) as i32;
+                vib_0_inner.push(vib_0_entry);
+                let vib_1_entry = (scale * ((63 - x) as f32 / 31.5 - 1.0)) as i32;
+                vib_1_inner.push(vib_1_entry);
+                let vib_2_entry = (scale * (if x < 32 { 1.0 } else { -1.0 })) as i32;
+                vib_2_inner.push(vib_2_entry);
+            }
+            vib_0.push(vib_0_inner);
+            vib_1.push(vib_1_inner);
+            vib_2.push(vib_2_inner);
+        }
+        vib_table.push(vib_0);
+        vib_table.push(vib_1);
+        vib_table.push(vib_2);
+
+        let channel_count = module.channel_count;
+        let mut player = Player {
+            module,
+            config,
+            p_table,
+            vib_table,
+            speed: 6,
+            bpm: 125,
+            tick_rate: 0,
+            tr_counter: 0,
+            cur_tick: 0,
+            cur_row: 0,
+            cur_pos: 0,
+            delay: 0,
+            position_jump: None,
+            pattern_break: None,
+            loop_jump: None,
+            visited: vec![0; 128],
+            loops: 0,
+            stopped: false,
+            finished: false,
+            channels: Vec::new(),
+            stereo_separation: 0.25,
+            led_filter: false,
+            filter: None,
+            muted: vec![false; channel_count],
+            solo: vec![false; channel_count],
+            channel_gain: vec![1.0; channel_count],
+            pan: (0..channel_count).map(default_pan).collect(),
+            levels: vec![0.0; channel_count],
+            voices: Vec::new(),
+            fades: Vec::new(),
+            voice_buf: Vec::new(),
+            fade_buf: Vec::new(),
+            sample_overrides: Vec::new(),
+        };
+
+        player.reset();
+        player.update_filter();
+        player
+    }
+
+    /// Puts the player back to the start of the song, as if newly created.
+    fn reset(&mut self) {
+        // Paula stuff
+
+        self.channels.clear();
+        self.voices.clear();
+        self.fades.clear();
+        for _ch in 0..self.module.channel_count {
+            self.channels.push(Channel::new());
+            self.voices.push(Voice::new());
+            self.fades.push(Voice::new());
+        }
+
+        self.speed = 6;
+        self.calc_tick_rate(self.module.initial_bpm());
+        self.tr_counter = 0;
+        self.cur_tick = 0;
+        self.cur_row = 0;
+        self.cur_pos = 0;
+        self.delay = 0;
+        self.position_jump = None;
+        self.pattern_break = None;
+        self.loop_jump = None;
+        self.visited.fill(0);
+        self.visited[0] = 1;
+        self.loops = 0;
+        self.stopped = false;
+        self.finished = false;
+        self.led_filter = false;
+        self.sample_overrides.clear();
+        self.sample_overrides
+            .resize(self.module.samples.len(), None);
+    }
+
+    /// Continues playback at `row` of position `order`. Channel state, effect
+    /// memory, speed and tempo are restored by playing the song up to there
+    /// without rendering it. Positions the song never reaches are jumped to
+    /// directly. Returns `false` if the position does not exist.
+    pub fn seek_to_order(&mut self, order: usize, row: usize) -> bool {
+        if order >= self.module.position_count || row >= 64 {
+            return false;
+        }
+        self.reset();
+        let clock_ratio = self.clock_ratio();
+        while self.cur_tick != 0 || self.cur_pos != order || self.cur_row != row {
+            if self.loops > 0 || self.finished {
+                self.reset();
+                self.cur_pos = order;
+                self.cur_row = row;
+                self.visited.fill(0);
+                self.visited[order] = 1 << row;
+                break;
+            }
+            self.tick();
+            for voice in self.voices.iter_mut() {
+                voice.skip(self.tick_rate, clock_ratio);
+            }
+        }
+        true
+    }
+
+    /// Continues playback at `time` from the start of the song, restoring
+    /// the state at that point like [`Player::seek_to_order`].
+    pub fn seek_to_time(&mut self, time: Duration) {
+        self.reset();
+        let frames = (time.as_secs_f64() * self.config.output_rate as f64) as usize;
+        self.skip(frames);
+    }
+
+    /// Advances the song by `frames` output frames without rendering them.
+    fn skip(&mut self, frames: usize) {
+        let clock_ratio = self.clock_ratio();
+        self.advance(frames, |player, samples, _| {
+            for voice in player.voices.iter_mut() {
+                voice.skip(samples, clock_ratio);
+            }
+            for fade in player.fades.iter_mut() {
+                fade.sample = None;
+            }
+        });
+    }
+
+    /// Runs the song for up to `frames` output frames, calling `render` with
+    /// the length and offset of every stretch between two ticks. Stops early
+    /// once the song has finished, returning the number of frames played.
+    fn advance(
+        &mut self,
+        frames: usize,
+        mut render: impl FnMut(&mut Player, usize, usize),
+    ) -> usize {
+        let mut len = frames;
+        let mut offset = 0;
+        while len > 0 {
+            let todo = cmp::min(len, self.tr_counter);
+            if todo > 0 {
+                render(self, todo, offset);
+                offset += todo;
+                len -= todo;
+                self.tr_counter -= todo;
+            } else if self.finished {
+                break;
+            } else {
+                self.tick();
+                self.tr_counter = self.tick_rate;
+            }
+        }
+        offset
+    }
+
+    /// Paula clock cycles per output sample.
+    fn clock_ratio(&self) -> f32 {
+        self.config.paula_clock.hz() as f32 / self.config.output_rate as f32
+    }
+
+    /// The song this player plays.
+    pub fn module(&self) -> &Arc<Module> {
+        &self.module
+    }
+
+    /// Output sample rate of [`Player::render`] in Hz.
+    pub fn sample_rate(&self) -> usize {
+        self.config.output_rate
+    }
+
+    pub fn config(&self) -> &PlayerConfig {
+        &self.config
+    }
+
+    /// Changes the output settings, e.g. once the audio device reported the
+    /// format it actually opened with.
+    pub fn set_config(&mut self, config: PlayerConfig) {
+        let filter_changed = config.amiga_model != self.config.amiga_model
+            || config.output_rate != self.config.output_rate
+            || config.channel_layout != self.config.channel_layout;
+        self.config = config;
+        self.finished = self.stopped || self.loops_exhausted();
+        self.calc_tick_rate(self.bpm);
+        self.tr_counter = cmp::min(self.tr_counter, self.tick_rate);
+        if filter_changed {
+            self.update_filter();
+        }
+    }
+
+    /// Sets up the output filters for the configured model and format.
+    fn update_filter(&mut self) {
+        self.filter = self.config.amiga_model.map(|model| {
+            AmigaFilter::new(
+                model,
+                self.config.output_rate,
+                self.config.channel_layout.channels(),
+            )
+        });
+    }
+
+    /// Whether the LED filter is switched on, see
+    /// [`PlayerConfig::amiga_model`].
+    pub fn led_filter(&self) -> bool {
+        self.led_filter
+    }
+
+    /// Silences channel `ch` in the mix. The channel keeps playing, so it
+    /// comes back in sync when unmuted.
+    pub fn set_channel_muted(&mut self, ch: usize, muted: bool) {
+        self.muted[ch] = muted;
+    }
+
+    pub fn is_channel_muted(&self, ch: usize) -> bool {
+        self.muted[ch]
+    }
+
+    /// While any channel is soloed, only the soloed channels are heard.
+    pub fn set_channel_solo(&mut self, ch: usize, solo: bool) {
+        self.solo[ch] = solo;
+    }
+
+    pub fn is_channel_solo(&self, ch: usize) -> bool {
+        self.solo[ch]
+    }
+
+    /// Scales the output of channel `ch`, 1.0 leaves it unchanged.
+    pub fn set_channel_gain(&mut self, ch: usize, gain: f32) {
+        self.channel_gain[ch] = gain;
+    }
+
+    pub fn channel_gain(&self, ch: usize) -> f32 {
+        self.channel_gain[ch]
+    }
+
+    /// Narrows the stereo image: 0.0 is mono, 1.0 the hard left and right
+    /// of an Amiga. Defaults to 0.25, which is easier on headphones.
+    pub fn set_stereo_separation(&mut self, separation: f32) {
+        self.stereo_separation = separation.clamp(0.0, 1.0);
+    }
+
+    pub fn stereo_separation(&self) -> f32 {
+        self.stereo_separation
+    }
+
+    /// Moves channel `ch` between -1.0 (left) and 1.0 (right), scaled by the
+    /// stereo separation. Channels start in the Amiga's LRRL layout. Pan
+    /// effects override this in [`PlayerConfig::extended_panning`] mode.
+    pub fn set_channel_pan(&mut self, ch: usize, pan: f32) {
+        self.pan[ch] = pan.clamp(-1.0, 1.0);
+    }
+
+    pub fn channel_pan(&self, ch: usize) -> f32 {
+        self.pan[ch]
+    }
+
+    /// Whether channel `ch` ends up in the mix with the current mute and
+    /// solo flags.
+    pub fn is_channel_audible(&self, ch: usize) -> bool {
+        if self.solo.contains(&true) {
+            self.solo[ch]
+        } else {
+            !self.muted[ch]
+        }
+    }
+
+    /// Peak level of channel `ch` during the last [`Player::render`] or
+    /// [`Player::render_channels`] call, as the voice plays it before the
+    /// mixer settings. 1.0 is a full scale sample at volume 64.
+    pub fn channel_level(&self, ch: usize) -> f32 {
+        self.levels[ch]
+    }
+
+    /// Period channel `ch` currently plays at, as Paula gets it.
+    pub fn channel_period(&self, ch: usize) -> usize {
+        self.voices[ch].period as usize
+    }
+
+    /// Bytes of sample `index` as they are played, including the loop bytes
+    /// inverted by `EFx`.
+    pub fn sample_data(&self, index: usize) -> &[i8] {
+        sample_bytes(&self.module.samples, &self.sample_overrides, index)
+    }
+
+    /// Position and row the next tick will play.
+    pub fn position(&self) -> (usize, usize) {
+        (self.cur_pos, self.cur_row)
+    }
+
+    fn calc_tick_rate(&mut self, bpm: usize) {
+        self.bpm = bpm;
+        // a tick shorter than a frame would never let the song advance
+        self.tick_rate = cmp::max(1, 125 * self.config.output_rate / (bpm * OUTFPS));
+    }
+
+    fn trig_note(&mut self, channel_index: usize, event: &Event) {
+        let mut offset: usize = 0;
+        if event.fx == 9 {
+            let channel = &mut self.channels[channel_index];
+            offset = channel.fx_buf[9] << 8;
+        }
+        let channel = &self.channels[channel_index];
+        // a sample number alone never had a note to set the period from
+        if event.fx != 3 && event.fx != 5 && channel.sample > 0 && channel.note > 0 {
+            let channel = &mut self.channels[channel_index];
+            let sample = &self.module.samples[channel.sample - 1];
+            channel.set_period(&self.p_table, 0, 0);
+
+            let voice: &mut Voice = &mut self.voices[channel_index];
+            if self.config.declick {
+                // the old note fades out under the new one, which fades in
+                if voice.sample.is_some() {
+                    let fade = &mut self.fades[channel_index];
+                    fade.clone_from(voice);
+                    fade.volume = 0;
+                }
+                voice.gain = 0.0;
+            }
+            let repeat = Repeat::of(channel.sample - 1, sample);
+            voice.trigger_repeat(repeat, sample, offset as isize);
+            if sample.length > 0 && offset >= sample.length * 2 {
+                if self.module.compatibility.cuts_offset_past_end() {
+                    voice.sample = None;
+                } else {
+                    // ProTracker plays on from the loop
+                    voice.pos = voice.sample_length as f32;
+                    voice.wrap();
+                }
+            }
+            if channel.vib_retr > 0 {
+                channel.vib_pos = 0;
+            }
+            if channel.trem_retr > 0 {
+                channel.trem_pos = 0;
+            }
+        }
+    }
+
+    /// Advances the `EFx` counter of channel `ch`, inverting the next byte of
+    /// the sample's loop each time it overflows. With `EF0` nothing happens.
+    fn invert_loop(&mut self, ch: usize) {
+        let channel = &mut self.channels[ch];
+        if channel.funk_speed == 0 || channel.sample == 0 {
+            return;
+        }
+        channel.funk_offset += FUNK_TABLE[channel.funk_speed];
+        if channel.funk_offset < 128 {
+            return;
+        }
+        channel.funk_offset = 0;
+
+        let index = channel.sample - 1;
+        let sample = &self.module.samples[index];
+        // samples without a loop repeat their first two bytes
+        let (loop_start, loop_len) = if sample.loop_len > 1 {
+            (sample.loop_start * 2, sample.loop_len * 2)
+        } else {
+            (0, 2)
+        };
+        channel.funk_pos += 1;
+        if channel.funk_pos >= loop_len {
+            channel.funk_pos = 0;
+        }
+        let data = self.sample_overrides[index].get_or_insert_with(|| sample.data.clone());
+        if let Some(byte) = data.get_mut(loop_start + channel.funk_pos) {
+            *byte = -1 - *byte;
+        }
+    }
+
+    fn tick(&mut self) {
+        for ch in 0..self.module.channel_count {
+            let pattern = &self.module.patterns[self.module.pattern_list[self.cur_pos]];
+            let row = &pattern.rows[self.cur_row];
+            let mut event = row.events[ch];
+            if self.module.format.is_soundtracker() && event.fx == 14 {
+                // no extended effects before ProTracker
+                event.fx = 0;
+                event.fx_param = 0;
+            }
+
+            let fxpl = event.fx_param & 0x0F;
+            let mut trem_vol: isize = 0;
+            let mut voice_period = None;
+            if self.cur_tick == 0 {
+                if event.sample > 0 && event.sample <= self.module.samples.len() {
+                    let channel = &mut self.channels[ch];
+                    channel.sample = event.sample;
+                    channel.fine_tune = self.module.samples[channel.sample - 1].finetune as isize;
+                    channel.volume = self.module.samples[channel.sample - 1].volume as usize;
+                    channel.funk_pos = 0;
+                    if event.note == 0 || event.fx == 3 || event.fx == 5 {
+                        // without a new note Paula finishes its current loop first
+                        let index = channel.sample - 1;
+                        let repeat = Repeat::of(index, &self.module.samples[index]);
+                        self.voices[ch].latch(repeat);
+                    }
+                }
+                if event.fx_param > 0 {
+                    let channel = &mut self.channels[ch];
+                    channel.fx_buf[event.fx] = event.fx_param
+                }
+                if event.note > 0 && (event.fx != 14 || ((event.fx_param >> 4) != 13)) {
+                    let channel = &mut self.channels[ch];
+                    channel.note = event.note;
+                    self.trig_note(ch, &event);
+                }
+
+                match event.fx {
+                    4 | 6 => {
+                        let channel = &mut self.channels[ch];
+                        if channel.fx_buf[4] & 0x0f > 0 {
+                            channel.vib_ampl = channel.fx_buf[4] & 0x0f;
+                        }
+                        if channel.fx_buf[4] & 0xf0 > 0 {
+                            channel.vib_speed = channel.fx_buf[4] >> 4;
+                        }
+                        if channel.vib_ampl > 0 {
+                            channel.set_period(
+                                &self.p_table,
+                                0,
+                                self.vib_table[channel.vib_wave][(channel.vib_ampl) - 1]
+                                    [channel.vib_pos] as isize
+                                    * self.module.compatibility.vibrato_depth(),
+                            );
+                        }
+                    }
+                    7 => {
+                        let channel = &mut self.channels[ch];
+                        if channel.fx_buf[7] & 0x0f > 0 {
+                            channel.trem_ampl = channel.fx_buf[7] & 0x0f;
+                        }
+                        if channel.fx_buf[7] & 0xf0 > 0 {
+                            channel.trem_speed = channel.fx_buf[7] >> 4;
+                        }
+                        if channel.trem_ampl > 0 {
+                            trem_vol = self.vib_table[channel.trem_wave][(channel.trem_ampl) - 1]
+                                [channel.trem_pos] as isize;
+                        }
+                    }
+                    8 if self.config.extended_panning => {
+                        let channel = &mut self.channels[ch];
+                        channel.pan = Some(pan_from_effect(event.fx_param));
+                    }
+                    11 => {
+                        self.position_jump = Some(event.fx_param);
+                        if self.module.compatibility.position_jump_clears_break() {
+                            self.pattern_break = None;
+                        }
+                    }
+                    12 => {
+                        let channel = &mut self.channels[ch];
+                        channel.volume = clamp(event.fx_param, 0, 64);
+                    }
+                    13 => {
+                        self.pattern_break =
+                            Some(10 * (event.fx_param >> 4) + (event.fx_param & 0x0F));
+                    }
+                    14 => {
+                        if fxpl > 0 {
+                            let channel = &mut self.channels[ch];
+                            channel.fx_buf14[event.fx_param >> 4] = fxpl;
+                        }
+                        match event.fx_param >> 4 {
+                            0 => self.led_filter = fxpl & 1 == 0,
+                            1 => {
+                                let channel = &mut self.channels[ch];
+                                channel.period = cmp::max(
+                                    113,
+                                    channel.period.saturating_sub(channel.fx_buf14[1]),
+                                );
+                            }
+                            2 => {
+                                let channel = &mut self.channels[ch];
+                                channel.period =
+                                    cmp::min(856, channel.period + channel.fx_buf14[1]);
+                            }
+                            3 => {
+                                let channel = &mut self.channels[ch];
+                                channel.glissando = fxpl != 0;
+                            }
+                            4 => {
+                                let channel = &mut self.channels[ch];
+                                channel.vib_wave = fxpl & 3;
+                                if channel.vib_wave == 3 {
+                                    channel.vib_wave = 0;
+                                }
+                                channel.vib_retr = fxpl & 4;
+                            }
+                            5 => {
+                                let channel = &mut self.channels[ch];
+                                channel.fine_tune = fxpl as isize;
+                                if channel.fine_tune >= 8 {
+                                    channel.fine_tune -= 16
+                                }
+                            }
+                            6 => {
+                                let channel = &mut self.channels[ch];
+                                if fxpl == 0 {
+                                    channel.loop_start = self.cur_row;
+                                } else if channel.loop_count < fxpl {
+                                    self.loop_jump = Some(channel.loop_start);
+                                    channel.loop_count += 1;
+                                } else {
+                                    channel.loop_count = 0;
+                                }
+                            }
+                            7 => {
+                                let channel = &mut self.channels[ch];
+                                channel.trem_wave = fxpl & 3;
+                                if channel.trem_wave == 3 {
+                                    channel.trem_wave = 0;
+                                }
+                                channel.trem_retr = fxpl & 4;
+                            }
+                            8 if self.config.extended_panning => {
+                                let channel = &mut self.channels[ch];
+                                channel.pan = Some(pan_from_effect(fxpl * 0x11));
+                            }
+                            9 => {
+                                let channel = &mut self.channels[ch];
+                                channel.retrig_count = 0;
+                                if channel.fx_buf14[9] > 0
+                                    && event.note == 0
+                                    && self.module.compatibility.retriggers_on_first_tick()
+                                {
+                                    self.trig_note(ch, &event);
+                                }
+                            }
+                            10 => {
+                                let channel = &mut self.channels[ch];
+                                channel.volume =
+                                    cmp::min(channel.volume + channel.fx_buf14[10], 64);
+                            }
+                            11 => {
+                                let channel = &mut self.channels[ch];
+                                channel.volume =
+                                    channel.volume.saturating_sub(channel.fx_buf14[11]);
+                            }
+                            14 => {
+                                let channel = &mut self.channels[ch];
+                                self.delay = channel.fx_buf14[14];
+                            }
+                            15 => {
+                                let channel = &mut self.channels[ch];
+                                channel.funk_speed = fxpl;
+                                self.invert_loop(ch);
+                            }
+                            _ => {}
+                        };
+                    }
+                    15 if event.fx_param == 0 && !self.module.format.is_soundtracker() => {
+                        // F00 stops ProTracker
+                        self.stopped = true;
+                        self.finished = true;
+                    }
+                    15 if event.fx_param > 0 => {
+                        if event.fx_param <= 32 || self.module.format.is_soundtracker() {
+                            self.speed = event.fx_param;
+                        } else {
+                            self.calc_tick_rate(event.fx_param);
+                        }
+                    }
+                    _ => {}
+                }
+            } else {
+                self.invert_loop(ch);
+                match event.fx {
+                    0 if event.fx_param > 0 => {
+                        // arpeggio
+                        let mut no: usize = 0;
+                        let channel = &mut self.channels[ch];
+                        match self.cur_tick % 3 {
+                            1 => no = event.fx_param >> 4,
+                            2 => no = event.fx_param & 0x0F,
+                            _ => {}
+                        }
+                        channel.set_period(&self.p_table, no as isize, 0);
+                    }
+                    1 => {
+                        // slide up
+                        let channel = &mut self.channels[ch];
+                        channel.period =
+                            cmp::max(113, channel.period.saturating_sub(channel.fx_buf[1]));
+                    }
+                    2 => {
+                        // slide down
+                        let channel = &mut self.channels[ch];
+                        channel.period = cmp::min(856, channel.period + channel.fx_buf[2]);
+                    }
+                    3 | 5 => {
+                        let channel = &mut self.channels[ch];
+                        // slide plus volslide
+                        if event.fx == 5 {
+                            if channel.fx_buf[5] & 0xf0 > 0 {
+                                channel.volume =
+                                    cmp::min(channel.volume + (channel.fx_buf[5] >> 4), 64);
+                            } else {
+                                channel.volume =
+                                    channel.volume.saturating_sub(channel.fx_buf[5] & 0x0F);
+                            }
+                        }
+                        let np = channel.get_period(&self.p_table, 0, 0);
+                        if channel.period > np {
+                            channel.period =
+                                cmp::max(channel.period.saturating_sub(channel.fx_buf[3]), np);
+                        } else {
+                            channel.period = cmp::min(channel.period + channel.fx_buf[3], np);
+                        }
+                        if channel.glissando {
+                            voice_period =
+                                Some(channel.snap_to_note(&self.p_table, channel.period));
+                        }
+                    }
+                    4 | 6 => {
+                        let channel = &mut self.channels[ch];
+                        if event.fx == 6 {
+                            if channel.fx_buf[6] & 0xf0 > 0 {
+                                channel.volume =
+                                    cmp::min(channel.volume + (channel.fx_buf[6] >> 4), 64);
+                            } else {
+                                channel.volume =
+                                    channel.volume.saturating_sub(channel.fx_buf[6] & 0x0F);
+                            }
+                        }
+                        if channel.vib_ampl > 0 {
+                            channel.set_period(
+                                &self.p_table,
+                                0,
+                                self.vib_table[channel.vib_wave][channel.vib_ampl - 1]
+                                    [channel.vib_pos] as isize
+                                    * self.module.compatibility.vibrato_depth(),
+                            );
+                        }
+                        channel.vib_pos = (channel.vib_pos + channel.vib_speed) & 0x3F;
+                    }
+                    7 => {
+                        let channel = &mut self.channels[ch];
+                        if channel.trem_ampl > 0 {
+                            trem_vol = self.vib_table[channel.trem_wave][channel.trem_ampl - 1]
+                                [channel.trem_pos] as isize;
+                        }
+                        channel.trem_pos = (channel.trem_pos + channel.trem_speed) & 0x3F;
+                    }
+                    10 => {
+                        let channel = &mut self.channels[ch];
+                        if channel.fx_buf[10] & 0xF0 > 0 {
+                            channel.volume =
+                                cmp::min(channel.volume + (channel.fx_buf[10] >> 4), 64);
+                        } else {
+                            channel.volume = cmp::max(
+                                channel.volume as isize - (channel.fx_buf[10] & 0x0F) as isize,
+                                0,
+                            ) as usize;
+                        }
+                    }
+                    14 => match event.fx_param >> 4 {
+                        9 => {
+                            let channel = &mut self.channels[ch];
+                            channel.retrig_count += 1;
+                            if channel.retrig_count == channel.fx_buf14[9] {
+                                channel.retrig_count = 0;
+                                self.trig_note(ch, &event);
+                            }
+                        }
+                        12 => {
+                            // cut
+                            let channel = &mut self.channels[ch];
+                            if self.cur_tick == channel.fx_buf14[12] {
+                                channel.volume = 0;
+                            }
+                        }
+                        13 => {
+                            // delay
+                            let channel = &mut self.channels[ch];
+                            if self.cur_tick == channel.fx_buf14[13] {
+                                self.trig_note(ch, &event)
+                            }
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+            let voice = &mut self.voices[ch];
+            let channel = &mut self.channels[ch];
+            voice.volume = clamp(channel.volume as isize + trem_vol, 0, 64);
+            voice.period = voice_period.unwrap_or(channel.period) as isize;
+        }
+
+        self.cur_tick += 1;
+        if self.cur_tick >= self.speed * (self.delay + 1) {
+            self.cur_tick = 0;
+            self.delay = 0;
+            self.next_row();
+        }
+    }
+
+    /// Moves on to the next row, following pattern loops, position jumps and
+    /// pattern breaks of the row just played, and detects the song's end.
+    fn next_row(&mut self) {
+        let position_jump = self.position_jump.take();
+        let pattern_break = self.pattern_break.take();
+        if let Some(loop_start) = self.loop_jump.take() {
+            // rows inside a pattern loop are played again on purpose
+            for row in loop_start..=self.cur_row {
+                self.visited[self.cur_pos] &= !(1 << row);
+            }
+            self.cur_row = loop_start;
+        } else if position_jump.is_some() || pattern_break.is_some() {
+            self.cur_pos = position_jump.unwrap_or(self.cur_pos + 1);
+            self.cur_row = pattern_break.filter(|&row| row < 64).unwrap_or(0);
+        } else {
+            self.cur_row += 1;
+            if self.cur_row >= 64 {
+                self.cur_row = 0;
+                self.cur_pos += 1;
+                // println!(
+                //     "NEXT_PATTERN POS:{} PTN: {}",
+                //     self.cur_pos, self.module.pattern_list[self.cur_pos]
+                // );
+            }
+        }
+        if self.cur_pos >= self.module.position_count {
+            self.cur_pos = 0;
+        }
+
+        if self.visited[self.cur_pos] & (1 << self.cur_row) != 0 {
+            // back at a row played before: the song has reached its end
+            self.loops += 1;
+            self.visited.fill(0);
+            self.finished = self.stopped || self.loops_exhausted();
+        }
+        self.visited[self.cur_pos] |= 1 << self.cur_row;
+    }
+
+    fn loops_exhausted(&self) -> bool {
+        match self.config.loop_mode {
+            LoopMode::Forever => false,
+            LoopMode::StopAtEnd => self.loops > 0,
+            LoopMode::Repeat(count) => self.loops > count,
+        }
+    }
+
+    /// Whether the song has ended according to the configured
+    /// [`LoopMode`]. A finished player only renders silence.
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.tr_counter == 0
+    }
+
+    /// How often the song has played to its end and started over.
+    pub fn loops(&self) -> usize {
+        self.loops
+    }
+
+    /// Works out how long the song plays until it ends, by running the same
+    /// effect processing as playback, but without rendering any audio.
+    pub fn duration(&self) -> SongDuration {
+        let config = PlayerConfig {
+            loop_mode: LoopMode::StopAtEnd,
+            ..self.config
+        };
+        let mut player = Player::with_config(self.module.clone(), config);
+        let mut positions = vec![None; self.module.position_count];
+        let mut samples: u64 = 0;
+        // songs caught in endless pattern loops are cut off
+        let max_samples = MAX_SONG_DURATION * config.output_rate as u64;
+        while !player.finished && samples < max_samples {
+            if player.cur_tick == 0 && positions[player.cur_pos].is_none() {
+                positions[player.cur_pos] = Some(samples);
+            }
+            player.tick();
+            samples += player.tick_rate as u64;
+        }
+
+        let to_duration = |samples: u64| {
+            Duration::from_nanos(samples * 1_000_000_000 / config.output_rate as u64)
+        };
+        SongDuration {
+            total: to_duration(samples),
+            positions: positions
+                .into_iter()
+                .map(|samples| samples.map(to_duration))
+                .collect(),
+        }
+    }
+
+    /// Volume change per output sample when declicking, or 0.
+    fn ramp(&self) -> f32 {
+        if self.config.declick {
+            1.0 / (RAMP_SECONDS * self.config.output_rate as f32)
+        } else {
+            0.0
+        }
+    }
+
+    /// Renders channel `ch` into `voice_buf`, along with the note it fades
+    /// out when declicking. Returns `false` if the channel is silent.
+    fn render_voice(&mut self, ch: usize, samples: usize) -> bool {
+        let clock_ratio = self.clock_ratio();
+        let ramp = self.ramp();
+        let interpolation = self.config.interpolation;
+        let (module, overrides) = (&self.module, &self.sample_overrides);
+        let sample_data = |index| sample_bytes(&module.samples, overrides, index);
+        let (voice, fade) = (&mut self.voices[ch], &mut self.fades[ch]);
+        if voice.sample.is_none() && fade.sample.is_none() {
+            return false;
+        }
+
+        self.voice_buf.resize(samples, 0.0);
+        if voice.sample.is_some() {
+            voice.render(
+                sample_data,
+                &mut self.voice_buf,
+                clock_ratio,
+                interpolation,
+                ramp,
+            );
+        } else {
+            self.voice_buf.fill(0.0);
+        }
+        if fade.sample.is_some() {
+            self.fade_buf.resize(samples, 0.0);
+            fade.render(
+                sample_data,
+                &mut self.fade_buf,
+                clock_ratio,
+                interpolation,
+                ramp,
+            );
+            for (out, value) in self.voice_buf.iter_mut().zip(self.fade_buf.iter()) {
+                *out += value;
+            }
+            if fade.gain == 0.0 {
+                fade.sample = None;
+            }
+        }
+        let peak = self
+            .voice_buf
+            .iter()
+            .fold(0.0, |peak: f32, value| peak.max(value.abs()));
+        self.levels[ch] = self.levels[ch].max(peak);
+        true
+    }
+
+    fn paula_render_channels(&mut self, bufs: &mut [&mut [f32]], samples: usize, offset: usize) {
+        for ch in 0..self.module.channel_count {
+            if self.render_voice(ch, samples) {
+                if let Some(buf) = bufs.get_mut(ch) {
+                    buf[offset..offset + samples].copy_from_slice(&self.voice_buf);
+                }
+            }
+        }
+    }
+
+    fn paula_render(&mut self, out_buf: &mut [f32], samples: usize, offset: usize) {
+        // keep the mix of many channels in the same range as the 4 channel mix
+        let gain = 2.0 / self.module.channel_count as f32;
+        for ch in 0..self.module.channel_count {
+            // silenced voices still render so they stay in sync for unmuting
+            let gain = if self.is_channel_audible(ch) {
+                gain * self.channel_gain[ch]
+            } else {
+                0.0
+            };
+            if self.render_voice(ch, samples) {
+                match self.config.channel_layout {
+                    ChannelLayout::Mono => {
+                        let out = &mut out_buf[offset..offset + samples];
+                        // the level of one side of a centred stereo mix
+                        for (out, value) in out.iter_mut().zip(self.voice_buf.iter()) {
+                            *out += value * gain * 0.5;
+                        }
+                    }
+                    ChannelLayout::Stereo => {
+                        let pan = self.channels[ch].pan.unwrap_or(self.pan[ch]);
+                        let pan = pan * self.stereo_separation;
+                        let (left, right) = ((1.0 - pan) * 0.5, (1.0 + pan) * 0.5);
+                        let out = &mut out_buf[offset * 2..(offset + samples) * 2];
+                        for (frame, value) in out.chunks_mut(2).zip(self.voice_buf.iter()) {
+                            frame[0] += value * gain * left;
+                            frame[1] += value * gain * right;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fills `buf` with frames in the configured channel layout, advancing
+    /// the song. Returns the number of frames played, which falls short of
+    /// `buf` once the song has finished; the rest is silence.
+    pub fn render(&mut self, buf: &mut [f32]) -> usize {
+        let frames = buf.len() / self.config.channel_layout.channels();
+        buf.fill(0.0);
+        self.levels.fill(0.0);
+        let channels = self.config.channel_layout.channels();
+        self.advance(frames, |player, samples, offset| {
+            player.paula_render(buf, samples, offset);
+            if let Some(filter) = &mut player.filter {
+                filter.process(
+                    &mut buf[offset * channels..(offset + samples) * channels],
+                    player.led_filter,
+                );
+            }
+        })
+    }
+
+    /// Renders every channel into its own buffer instead of mixing them,
+    /// advancing the song like [`Player::render`]. The buffers get mono
+    /// samples as the voice plays them, before panning, mixer gain, mute or
+    /// solo and the output filters. All buffers are filled up to the length
+    /// of the shortest one; channels without a buffer are played but
+    /// dropped. Returns the number of frames played.
+    pub fn render_channels(&mut self, bufs: &mut [&mut [f32]]) -> usize {
+        let frames = bufs.iter().map(|buf| buf.len()).min().unwrap_or(0);
+        for buf in bufs.iter_mut() {
+            buf.fill(0.0);
+        }
+        self.levels.fill(0.0);
+        self.advance(frames, |player, samples, offset| {
+            player.paula_render_channels(bufs, samples, offset)
+        })
+    }
+}
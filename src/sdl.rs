@@ -0,0 +1,68 @@
+//! [`AudioSink`] for SDL2 playback devices.
+
+use crate::config::ChannelLayout;
+use crate::handle::PlayerRenderer;
+use crate::sink::AudioSink;
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::AudioSubsystem;
+use std::io;
+
+struct Callback {
+    renderer: Option<PlayerRenderer>,
+}
+
+impl AudioCallback for Callback {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        match &mut self.renderer {
+            Some(renderer) => {
+                renderer.render(out);
+            }
+            None => out.fill(0.0),
+        }
+    }
+}
+
+/// Plays on an SDL2 audio device, rendering from SDL's audio thread.
+/// Playback stops when the sink is dropped.
+pub struct SdlSink {
+    device: AudioDevice<Callback>,
+}
+
+impl SdlSink {
+    /// Opens the default playback device, asking for `output_rate` Hz and
+    /// `channel_layout`. The device may settle on another format, which
+    /// [`AudioSink::format`] reports.
+    pub fn open(
+        audio: &AudioSubsystem,
+        output_rate: usize,
+        channel_layout: ChannelLayout,
+    ) -> Result<SdlSink, String> {
+        let desired_spec = AudioSpecDesired {
+            freq: Some(output_rate as i32),
+            channels: Some(channel_layout.channels() as u8),
+            samples: None, // default sample size
+        };
+        let device = audio.open_playback(None, &desired_spec, |_| Callback { renderer: None })?;
+        Ok(SdlSink { device })
+    }
+}
+
+impl AudioSink for SdlSink {
+    fn format(&self) -> (usize, ChannelLayout) {
+        let spec = self.device.spec();
+        let channel_layout = if spec.channels == 1 {
+            ChannelLayout::Mono
+        } else {
+            ChannelLayout::Stereo
+        };
+        (spec.freq as usize, channel_layout)
+    }
+
+    fn play(&mut self, renderer: PlayerRenderer) -> io::Result<()> {
+        self.device.lock().renderer = Some(renderer);
+        self.device.resume();
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! Outputs for rendered audio. A sink pulls frames from a
+//! [`PlayerRenderer`]: sound devices from their own audio thread, files and
+//! the null sink right away until the song ends.
+
+use crate::config::{ChannelLayout, PlayerConfig};
+use crate::handle::{PlayerHandle, PlayerRenderer};
+use crate::player::Player;
+use crate::wav::{WavFormat, WavWriter};
+use std::cmp;
+use std::io::{self, Seek, Write};
+use std::time::Duration;
+
+/// Frames rendered at a time by the offline sinks.
+const BLOCK_FRAMES: usize = 4096;
+
+/// Somewhere the audio of a player goes.
+pub trait AudioSink {
+    /// Output rate in Hz and channel layout the sink takes.
+    fn format(&self) -> (usize, ChannelLayout);
+
+    /// Plays what `renderer` renders. Real-time sinks return as soon as
+    /// playback runs, offline sinks once the song has been rendered.
+    fn play(&mut self, renderer: PlayerRenderer) -> io::Result<()>;
+}
+
+impl Player {
+    /// Sets the player up for the format of `sink` and starts playing on it.
+    /// The returned handle controls playback.
+    pub fn play_on(mut self, sink: &mut dyn AudioSink) -> io::Result<PlayerHandle> {
+        let (output_rate, channel_layout) = sink.format();
+        self.set_config(PlayerConfig {
+            output_rate,
+            channel_layout,
+            ..*self.config()
+        });
+        let (handle, renderer) = self.split();
+        sink.play(renderer)?;
+        Ok(handle)
+    }
+}
+
+/// Renders until the song has finished or `limit` is reached, passing each
+/// block to `write`.
+fn render_offline(
+    mut renderer: PlayerRenderer,
+    limit: Option<Duration>,
+    mut write: impl FnMut(&[f32]) -> io::Result<()>,
+) -> io::Result<()> {
+    let config = *renderer.player().config();
+    let channels = config.channel_layout.channels();
+    let mut frames_left = match limit {
+        Some(limit) => (limit.as_secs_f64() * config.output_rate as f64) as usize,
+        None => usize::MAX,
+    };
+    let mut buf = vec![0.0; BLOCK_FRAMES * channels];
+    while frames_left > 0 && !renderer.player().is_finished() {
+        let frames = cmp::min(frames_left, BLOCK_FRAMES);
+        let played = renderer.render(&mut buf[..frames * channels]);
+        write(&buf[..played * channels])?;
+        frames_left -= frames;
+    }
+    Ok(())
+}
+
+/// Layout of the file a [`FileSink`] writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Wav(WavFormat),
+    /// Interleaved little-endian samples without a header
+    Raw(WavFormat),
+}
+
+/// Writes the song to a file, as fast as it renders. Songs that loop
+/// forever need a limit.
+pub struct FileSink<W: Write + Seek> {
+    writer: Option<W>,
+    format: FileFormat,
+    output_rate: usize,
+    channel_layout: ChannelLayout,
+    limit: Option<Duration>,
+}
+
+impl<W: Write + Seek> FileSink<W> {
+    pub fn new(
+        writer: W,
+        format: FileFormat,
+        output_rate: usize,
+        channel_layout: ChannelLayout,
+    ) -> FileSink<W> {
+        FileSink {
+            writer: Some(writer),
+            format,
+            output_rate,
+            channel_layout,
+            limit: None,
+        }
+    }
+
+    /// Stops writing after `limit` even if the song goes on.
+    pub fn with_limit(mut self, limit: Duration) -> FileSink<W> {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns the underlying writer, or `None` if writing to it failed.
+    pub fn into_inner(self) -> Option<W> {
+        self.writer
+    }
+}
+
+impl<W: Write + Seek> AudioSink for FileSink<W> {
+    fn format(&self) -> (usize, ChannelLayout) {
+        (self.output_rate, self.channel_layout)
+    }
+
+    fn play(&mut self, renderer: PlayerRenderer) -> io::Result<()> {
+        let writer = self
+            .writer
+            .take()
+            .ok_or_else(|| io::Error::other("file sink already failed"))?;
+        let writer = match self.format {
+            FileFormat::Wav(format) => {
+                let mut wav = WavWriter::new(
+                    writer,
+                    self.output_rate,
+                    self.channel_layout.channels(),
+                    format,
+                )?;
+                render_offline(renderer, self.limit, |samples| wav.write(samples))?;
+                wav.finish()?
+            }
+            FileFormat::Raw(format) => {
+                let mut writer = writer;
+                render_offline(renderer, self.limit, |samples| {
+                    writer.write_all(&format.encode(samples))
+                })?;
+                writer.flush()?;
+                writer
+            }
+        };
+        self.writer = Some(writer);
+        Ok(())
+    }
+}
+
+/// Renders the song and throws the audio away, as fast as it can. Meant for
+/// tests and benchmarks.
+pub struct NullSink {
+    output_rate: usize,
+    channel_layout: ChannelLayout,
+    limit: Option<Duration>,
+    frames: usize,
+}
+
+impl NullSink {
+    pub fn new(output_rate: usize, channel_layout: ChannelLayout) -> NullSink {
+        NullSink {
+            output_rate,
+            channel_layout,
+            limit: None,
+            frames: 0,
+        }
+    }
+
+    /// Stops after `limit` even if the song goes on.
+    pub fn with_limit(mut self, limit: Duration) -> NullSink {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Frames rendered so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+impl AudioSink for NullSink {
+    fn format(&self) -> (usize, ChannelLayout) {
+        (self.output_rate, self.channel_layout)
+    }
+
+    fn play(&mut self, renderer: PlayerRenderer) -> io::Result<()> {
+        let channels = self.channel_layout.channels();
+        let frames = &mut self.frames;
+        render_offline(renderer, self.limit, |samples| {
+            *frames += samples.len() / channels;
+            Ok(())
+        })
+    }
+}
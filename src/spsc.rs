@@ -0,0 +1,92 @@
+//! Bounded single producer, single consumer queue, which passes values
+//! between two threads without locks or allocation.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Ring<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// Number of values ever popped, only written by the consumer
+    head: AtomicUsize,
+    /// Number of values ever pushed, only written by the producer
+    tail: AtomicUsize,
+}
+
+// The producer only touches slots between `tail` and `head + capacity`, the
+// consumer only those between `head` and `tail`, so no slot is ever shared.
+unsafe impl<T: Send> Send for Ring<T> {}
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    fn slot(&self, count: usize) -> *mut MaybeUninit<T> {
+        // the capacity is a power of two, so this survives the counters
+        // wrapping around
+        self.slots[count & (self.slots.len() - 1)].get()
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            unsafe { (*self.slot(head)).assume_init_drop() };
+            head = head.wrapping_add(1);
+        }
+    }
+}
+
+/// Sending end of a queue made by [`queue`].
+pub(crate) struct Producer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+/// Receiving end of a queue made by [`queue`].
+pub(crate) struct Consumer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+/// Makes a queue holding at least `capacity` values.
+pub(crate) fn queue<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    let slots = (0..capacity.max(1).next_power_of_two())
+        .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+        .collect();
+    let ring = Arc::new(Ring {
+        slots,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (Producer { ring: ring.clone() }, Consumer { ring })
+}
+
+impl<T> Producer<T> {
+    /// Appends `value`, or hands it back if the queue is full.
+    pub(crate) fn push(&mut self, value: T) -> Result<(), T> {
+        let ring = &*self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        let head = ring.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == ring.slots.len() {
+            return Err(value);
+        }
+        unsafe { (*ring.slot(tail)).write(value) };
+        ring.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+}
+
+impl<T> Consumer<T> {
+    /// Takes the oldest value, if there is one.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        let ring = &*self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        let tail = ring.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        let value = unsafe { (*ring.slot(head)).assume_init_read() };
+        ring.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+}
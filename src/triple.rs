@@ -0,0 +1,76 @@
+//! Triple buffer, which passes the latest of a stream of values from one
+//! thread to another without locks or allocation. Values the reader does not
+//! get to in time are replaced by newer ones.
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Set in `back` while its slot holds a value the reader has not taken
+const FRESH: usize = 4;
+
+struct Slots<T> {
+    slots: [UnsafeCell<T>; 3],
+    /// The slot neither side is using, with `FRESH` set if the writer filled
+    /// it last
+    back: AtomicUsize,
+}
+
+// The writer and the reader each own one slot and only trade it for the
+// back slot through `back`, so no slot is ever shared.
+unsafe impl<T: Send> Send for Slots<T> {}
+unsafe impl<T: Send> Sync for Slots<T> {}
+
+/// Writing end of a buffer made by [`buffer`].
+pub(crate) struct Writer<T> {
+    slots: Arc<Slots<T>>,
+    index: usize,
+}
+
+/// Reading end of a buffer made by [`buffer`].
+pub(crate) struct Reader<T> {
+    slots: Arc<Slots<T>>,
+    index: usize,
+}
+
+/// Makes a buffer that reads as `initial` until the first write.
+pub(crate) fn buffer<T: Copy>(initial: T) -> (Writer<T>, Reader<T>) {
+    let slots = Arc::new(Slots {
+        slots: [
+            UnsafeCell::new(initial),
+            UnsafeCell::new(initial),
+            UnsafeCell::new(initial),
+        ],
+        back: AtomicUsize::new(1),
+    });
+    (
+        Writer {
+            slots: slots.clone(),
+            index: 0,
+        },
+        Reader { slots, index: 2 },
+    )
+}
+
+impl<T> Writer<T> {
+    /// Publishes `value`, replacing any value the reader has not taken yet.
+    pub(crate) fn write(&mut self, value: T) {
+        let slots = &*self.slots;
+        unsafe { *slots.slots[self.index].get() = value };
+        let back = slots.back.swap(self.index | FRESH, Ordering::AcqRel);
+        self.index = back & !FRESH;
+    }
+}
+
+impl<T> Reader<T> {
+    /// The value written last, or the one read last if nothing was written
+    /// since.
+    pub(crate) fn read(&mut self) -> &T {
+        let slots = &*self.slots;
+        if slots.back.load(Ordering::Relaxed) & FRESH != 0 {
+            let back = slots.back.swap(self.index, Ordering::AcqRel);
+            self.index = back & !FRESH;
+        }
+        unsafe { &*slots.slots[self.index].get() }
+    }
+}
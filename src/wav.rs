@@ -0,0 +1,103 @@
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// Sample encoding of a WAV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavFormat {
+    /// 16 bit signed integer PCM
+    Int16,
+    /// 32 bit IEEE float
+    Float32,
+}
+
+impl WavFormat {
+    fn bytes_per_sample(&self) -> usize {
+        match self {
+            WavFormat::Int16 => 2,
+            WavFormat::Float32 => 4,
+        }
+    }
+
+    /// Little-endian bytes of `samples`, clipped to -1.0..1.0 for integer
+    /// output.
+    pub(crate) fn encode(&self, samples: &[f32]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(samples.len() * self.bytes_per_sample());
+        for &sample in samples {
+            match self {
+                WavFormat::Int16 => {
+                    let value = (sample.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                WavFormat::Float32 => bytes.extend_from_slice(&sample.to_le_bytes()),
+            }
+        }
+        bytes
+    }
+}
+
+/// Writes interleaved `f32` audio as it comes out of [`Player::render`] to a
+/// WAV file. The header sizes are filled in by [`WavWriter::finish`].
+///
+/// [`Player::render`]: crate::Player::render
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    format: WavFormat,
+    data_length: usize,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(
+        mut writer: W,
+        sample_rate: usize,
+        channels: usize,
+        format: WavFormat,
+    ) -> io::Result<WavWriter<W>> {
+        let block_align = channels * format.bytes_per_sample();
+        let format_tag: u16 = match format {
+            WavFormat::Int16 => 1,
+            WavFormat::Float32 => 3,
+        };
+
+        writer.write_all(b"RIFF")?;
+        // sizes are patched in finish()
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&format_tag.to_le_bytes())?;
+        writer.write_all(&(channels as u16).to_le_bytes())?;
+        writer.write_all(&(sample_rate as u32).to_le_bytes())?;
+        writer.write_all(&((sample_rate * block_align) as u32).to_le_bytes())?;
+        writer.write_all(&(block_align as u16).to_le_bytes())?;
+        writer.write_all(&((format.bytes_per_sample() * 8) as u16).to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        Ok(WavWriter {
+            writer,
+            format,
+            data_length: 0,
+        })
+    }
+
+    /// Appends interleaved samples, clipping them to -1.0..1.0 for integer
+    /// output.
+    pub fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        let bytes = self.format.encode(samples);
+        self.writer.write_all(&bytes)?;
+        self.data_length += bytes.len();
+        Ok(())
+    }
+
+    /// Fills in the chunk sizes and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&((36 + self.data_length) as u32).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer
+            .write_all(&(self.data_length as u32).to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
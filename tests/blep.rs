@@ -0,0 +1,45 @@
+mod common;
+
+use common::{cell, module, render_ticks, C2};
+use protracktor::{Interpolation, Player, PlayerConfig};
+use std::sync::Arc;
+
+/// Left side of the first tick of C-2 on a constant level, which Paula
+/// holds from the first byte on.
+fn play_level(interpolation: Interpolation) -> Vec<f32> {
+    let module = module(&[(0, 0, cell(1, C2, 0, 0))], &[100; 64], (0, 32));
+    let config = PlayerConfig {
+        interpolation,
+        ..PlayerConfig::default()
+    };
+    let mut player = Player::with_config(Arc::new(module), config);
+    render_ticks(&mut player, 1)
+        .into_iter()
+        .step_by(2)
+        .collect()
+}
+
+#[test]
+fn blep_step_settles_to_the_held_level() {
+    let held = play_level(Interpolation::Nearest);
+    let blep = play_level(Interpolation::Blep);
+    assert!(held.iter().all(|&value| value == held[0] && value > 0.0));
+
+    // the step is spread over its first samples instead of jumping
+    assert!((blep[0] - held[0]).abs() > 0.01);
+    // and has settled after its 16 samples
+    for value in &blep[16..] {
+        assert!((value - held[0]).abs() < 1e-4, "{value}");
+    }
+}
+
+#[test]
+fn blep_does_not_ring_far_past_the_step() {
+    let held = play_level(Interpolation::Nearest)[0];
+    let blep = play_level(Interpolation::Blep);
+    let peak = blep
+        .iter()
+        .fold(0.0, |peak: f32, value| peak.max(value.abs()));
+    // a band-limited step overshoots by about a tenth at most
+    assert!(peak < held * 1.15, "{peak}");
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::{cell, player, render_ticks, C2};
+use protracktor::Module;
+
+/// A module tagged `tag` playing `order`, with `patterns` stored as given,
+/// each as 64 rows of `stored_channels` cells set from `(row, channel, cell)`.
+/// Sample 1 holds a constant level and loops.
+fn tagged_module(
+    tag: &[u8; 4],
+    stored_channels: usize,
+    order: &[u8],
+    patterns: &[&[(usize, usize, [u8; 4])]],
+) -> Module {
+    let sample = [100i8; 64];
+    let mut data = vec![0; 20];
+    for index in 0..31 {
+        let mut header = [0; 30];
+        if index == 0 {
+            header[22..24].copy_from_slice(&(sample.len() as u16 / 2).to_be_bytes());
+            header[25] = 64;
+            header[28..30].copy_from_slice(&32u16.to_be_bytes());
+        } else {
+            header[29] = 1;
+        }
+        data.extend_from_slice(&header);
+    }
+    data.push(order.len() as u8);
+    data.push(0x7f);
+    let mut order_list = [0; 128];
+    order_list[..order.len()].copy_from_slice(order);
+    data.extend_from_slice(&order_list);
+    data.extend_from_slice(tag);
+
+    for cells in patterns {
+        let mut pattern = vec![0; 64 * stored_channels * 4];
+        for &(row, channel, cell) in cells.iter() {
+            let offset = (row * stored_channels + channel) * 4;
+            pattern[offset..offset + 4].copy_from_slice(&cell);
+        }
+        data.extend_from_slice(&pattern);
+    }
+    data.extend(sample.iter().map(|&byte| byte as u8));
+    Module::load(&data).unwrap()
+}
+
+#[test]
+fn channel_counts_come_from_the_tag() {
+    let tags: [(&[u8; 4], usize); 10] = [
+        (b"M.K.", 4),
+        (b"FLT4", 4),
+        (b"6CHN", 6),
+        (b"8CHN", 8),
+        (b"CD81", 8),
+        (b"OKTA", 8),
+        (b"OCTA", 8),
+        (b"12CH", 12),
+        (b"16CN", 16),
+        (b"32CH", 32),
+    ];
+    for (tag, channels) in tags {
+        let module = tagged_module(tag, channels, &[0], &[&[]]);
+        let name = String::from_utf8_lossy(tag);
+        assert_eq!(module.channel_count(), channels, "{name}");
+        let cells = module.patterns()[0].row(0).unwrap().cells().count();
+        assert_eq!(cells, channels, "{name}");
+    }
+}
+
+#[test]
+fn flt8_joins_pattern_halves() {
+    // four stored halves, each marked with its number in channel 0
+    let halves: Vec<Vec<_>> = (0..4)
+        .map(|half| vec![(0, 0, cell(0, 0, 0xc, half))])
+        .collect();
+    let halves: Vec<&[_]> = halves.iter().map(|half| half.as_slice()).collect();
+    let module = tagged_module(b"FLT8", 4, &[0, 2, 0], &halves);
+
+    assert_eq!(module.channel_count(), 8);
+    assert_eq!(module.patterns().len(), 2);
+    assert_eq!(module.pattern_list()[..3], [0, 1, 0]);
+    for (pattern, halves) in module.patterns().iter().zip([(0, 1), (2, 3)]) {
+        let row = pattern.row(0).unwrap();
+        assert_eq!(row.cell(0).unwrap().effect_param(), halves.0);
+        assert_eq!(row.cell(4).unwrap().effect_param(), halves.1);
+    }
+}
+
+#[test]
+fn channels_pan_in_the_amiga_layout() {
+    let player = player(tagged_module(b"8CHN", 8, &[0], &[&[]]));
+    let pans: Vec<f32> = (0..8).map(|ch| player.channel_pan(ch)).collect();
+    assert_eq!(pans, [-1.0, 1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0]);
+}
+
+#[test]
+fn every_voice_of_an_8_channel_module_plays() {
+    let cells: Vec<_> = (0..8).map(|ch| (0, ch, cell(1, C2, 0, 0))).collect();
+    let mut player = player(tagged_module(b"8CHN", 8, &[0], &[&cells]));
+    render_ticks(&mut player, 1);
+    for ch in 0..8 {
+        assert!(player.channel_level(ch) > 0.5, "channel {ch}");
+    }
+}
+
+#[test]
+fn upper_channels_reach_their_side_of_the_mix() {
+    for ch in 4..8 {
+        let cells = [(0, ch, cell(1, C2, 0, 0))];
+        let mut player = player(tagged_module(b"8CHN", 8, &[0], &[&cells]));
+        let output = render_ticks(&mut player, 1);
+        let left: f32 = output.iter().step_by(2).map(|value| value.abs()).sum();
+        let right: f32 = output
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .map(|value| value.abs())
+            .sum();
+        assert!(left + right > 0.0, "channel {ch}");
+        assert_eq!(left > right, player.channel_pan(ch) < 0.0, "channel {ch}");
+    }
+}
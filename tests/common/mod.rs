@@ -0,0 +1,78 @@
+//! Small hand-made modules for the effect tests.
+#![allow(dead_code)]
+
+use protracktor::{Module, Player};
+use std::sync::Arc;
+
+pub const C2: u16 = 428;
+pub const C3: u16 = 214;
+/// Output frames per tick at the default 48 kHz and 125 BPM.
+pub const TICK: usize = 960;
+
+/// One pattern cell: sample number, period, effect and effect parameter.
+pub fn cell(sample: u8, period: u16, effect: u8, param: u8) -> [u8; 4] {
+    [
+        (sample & 0xf0) | (period >> 8) as u8,
+        period as u8,
+        (sample << 4) | effect,
+        param,
+    ]
+}
+
+/// A four channel ProTracker module with a single sample and a single
+/// pattern holding `cells` as `(row, channel, cell)`. The sample loops over
+/// `repeat` as `(start, length)` in words.
+pub fn module(cells: &[(usize, usize, [u8; 4])], sample: &[i8], repeat: (usize, usize)) -> Module {
+    module_with_samples(cells, &[(sample, repeat)])
+}
+
+/// Like `module`, with each of `samples` given as its data and loop.
+pub fn module_with_samples(
+    cells: &[(usize, usize, [u8; 4])],
+    samples: &[(&[i8], (usize, usize))],
+) -> Module {
+    let mut data = vec![0; 20];
+    for index in 0..31 {
+        let mut header = [0; 30];
+        if let Some(&(sample, repeat)) = samples.get(index) {
+            header[22..24].copy_from_slice(&(sample.len() as u16 / 2).to_be_bytes());
+            header[25] = 64;
+            header[26..28].copy_from_slice(&(repeat.0 as u16).to_be_bytes());
+            header[28..30].copy_from_slice(&(repeat.1 as u16).to_be_bytes());
+        } else {
+            header[29] = 1;
+        }
+        data.extend_from_slice(&header);
+    }
+    data.push(1);
+    data.push(0x7f);
+    data.extend_from_slice(&[0; 128]);
+    data.extend_from_slice(b"M.K.");
+
+    let mut pattern = vec![0; 64 * 4 * 4];
+    for &(row, channel, cell) in cells {
+        let offset = (row * 4 + channel) * 4;
+        pattern[offset..offset + 4].copy_from_slice(&cell);
+    }
+    data.extend_from_slice(&pattern);
+    for (sample, _) in samples {
+        data.extend(sample.iter().map(|&byte| byte as u8));
+    }
+    Module::load(&data).unwrap()
+}
+
+pub fn player(module: Module) -> Player {
+    Player::new(Arc::new(module))
+}
+
+/// Plays `ticks` ticks and returns the stereo output.
+pub fn render_ticks(player: &mut Player, ticks: usize) -> Vec<f32> {
+    let mut buf = vec![0.0; ticks * TICK * 2];
+    player.render(&mut buf);
+    buf
+}
+
+/// Whether every sample of `output` is exactly zero.
+pub fn is_silent(output: &[f32]) -> bool {
+    output.iter().all(|&value| value == 0.0)
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::{cell, is_silent, module, player, render_ticks, C2, C3, TICK};
+use protracktor::{CompatibilityProfile, Module, Player};
+
+fn play(
+    cells: &[(usize, usize, [u8; 4])],
+    sample: &[i8],
+    repeat: (usize, usize),
+    compatibility: CompatibilityProfile,
+) -> Player {
+    let mut module = module(cells, sample, repeat);
+    module.compatibility = compatibility;
+    player(module)
+}
+
+#[test]
+fn protracker_modules_are_detected() {
+    let module = module(&[(0, 0, cell(1, C2, 0, 0))], &[0; 64], (0, 1));
+    assert_eq!(module.compatibility, CompatibilityProfile::ProTracker23);
+}
+
+#[test]
+fn pc_channel_tags_point_to_fasttracker() {
+    let mut data = module(&[(0, 0, cell(1, C2, 0, 0))], &[0; 64], (0, 1)).save();
+    data[1080..1084].copy_from_slice(b"4CHN");
+    let module = Module::load(&data).unwrap();
+    assert_eq!(module.compatibility, CompatibilityProfile::FastTracker);
+}
+
+#[test]
+fn panning_effects_keep_protracker() {
+    // Amiga demos use `8xx` and `E8x` as sync triggers
+    let cells = [(0, 0, cell(1, C2, 8, 0x40)), (1, 0, cell(0, 0, 0xe, 0x81))];
+    let module = module(&cells, &[0; 64], (0, 1));
+    assert_eq!(module.compatibility, CompatibilityProfile::ProTracker23);
+}
+
+#[test]
+fn retrigger_on_the_first_tick_depends_on_the_profile() {
+    // a short one-shot sample that is over well within the first tick
+    let mut sample = vec![100; 62];
+    sample.extend_from_slice(&[0, 0]);
+    let cells = [(0, 0, cell(1, C3, 0, 0)), (1, 0, cell(0, 0, 0xe, 0x93))];
+
+    let mut player = play(&cells, &sample, (0, 1), CompatibilityProfile::ProTracker23);
+    let output = render_ticks(&mut player, 7);
+    assert!(!is_silent(&output[6 * TICK * 2..]));
+
+    let mut player = play(&cells, &sample, (0, 1), CompatibilityProfile::FastTracker);
+    let output = render_ticks(&mut player, 10);
+    assert!(is_silent(&output[6 * TICK * 2..9 * TICK * 2]));
+    assert!(!is_silent(&output[9 * TICK * 2..]));
+}
+
+#[test]
+fn offset_past_the_sample_end_depends_on_the_profile() {
+    let cells = [(0, 0, cell(1, C2, 9, 0x01))];
+
+    let mut player = play(
+        &cells,
+        &[100; 64],
+        (0, 32),
+        CompatibilityProfile::ProTracker23,
+    );
+    assert!(!is_silent(&render_ticks(&mut player, 1)));
+
+    let mut player = play(
+        &cells,
+        &[100; 64],
+        (0, 32),
+        CompatibilityProfile::FastTracker,
+    );
+    assert!(is_silent(&render_ticks(&mut player, 1)));
+}
+
+#[test]
+fn position_jump_after_pattern_break_depends_on_the_profile() {
+    let cells = [(0, 0, cell(0, 0, 0xd, 0x10)), (0, 1, cell(0, 0, 0xb, 0x00))];
+
+    let mut player = play(&cells, &[0; 64], (0, 1), CompatibilityProfile::ProTracker23);
+    render_ticks(&mut player, 6);
+    assert_eq!(player.position(), (0, 0));
+
+    let mut player = play(&cells, &[0; 64], (0, 1), CompatibilityProfile::FastTracker);
+    render_ticks(&mut player, 6);
+    assert_eq!(player.position(), (0, 10));
+}
+
+#[test]
+fn protracker_1_vibrato_is_deeper() {
+    let cells = [(0, 0, cell(1, C2, 4, 0x48))];
+    let deviation = |compatibility| {
+        let mut player = play(&cells, &[100; 64], (0, 32), compatibility);
+        (0..6)
+            .map(|_| {
+                render_ticks(&mut player, 1);
+                (player.channel_period(0) as isize - C2 as isize).abs()
+            })
+            .max()
+            .unwrap()
+    };
+    assert!(
+        deviation(CompatibilityProfile::ProTracker1)
+            > deviation(CompatibilityProfile::ProTracker23)
+    );
+}
@@ -0,0 +1,20 @@
+mod common;
+
+use common::{cell, module, C2};
+use protracktor::{LoopMode, Player, PlayerConfig};
+use std::sync::Arc;
+
+#[test]
+fn output_rates_below_the_tick_rate_still_play() {
+    let module = module(&[(0, 0, cell(1, C2, 0, 0))], &[100; 64], (0, 32));
+    let config = PlayerConfig {
+        output_rate: 40,
+        loop_mode: LoopMode::StopAtEnd,
+        ..PlayerConfig::default()
+    };
+    let mut player = Player::with_config(Arc::new(module), config);
+    // one tick per frame, 64 rows of six ticks
+    let mut buf = vec![0.0; 2 * 64 * 6];
+    player.render(&mut buf);
+    assert!(player.is_finished());
+}
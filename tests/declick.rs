@@ -0,0 +1,60 @@
+mod common;
+
+use common::{cell, module, player, render_ticks, C2, TICK};
+use protracktor::{Player, PlayerConfig};
+
+/// A looped triangle wave, which plays without clicks of its own.
+fn triangle() -> Vec<i8> {
+    (0..64)
+        .map(|index: i32| (120 - (index - 32).abs() * 120 / 32) as i8)
+        .collect()
+}
+
+fn play(cells: &[(usize, usize, [u8; 4])], declick: bool) -> Player {
+    let mut player = player(module(cells, &triangle(), (0, 32)));
+    player.set_config(PlayerConfig {
+        declick,
+        ..*player.config()
+    });
+    player
+}
+
+/// Largest difference between two consecutive frames of the left channel.
+fn largest_step(output: &[f32]) -> f32 {
+    let left: Vec<f32> = output.iter().step_by(2).copied().collect();
+    left.windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn declicking_is_off_by_default() {
+    assert!(!PlayerConfig::default().declick);
+}
+
+#[test]
+fn note_cut_ramps_down_when_declicking() {
+    let cells = [(0, 0, cell(1, C2, 0xe, 0xc2))];
+
+    let output = render_ticks(&mut play(&cells, false), 3);
+    assert!(output[2 * TICK * 2..].iter().all(|&value| value == 0.0));
+
+    let output = render_ticks(&mut play(&cells, true), 3);
+    let cut = 2 * TICK * 2;
+    assert!(output[cut..cut + 20].iter().all(|&value| value != 0.0));
+    // four milliseconds are 192 frames at 48 kHz
+    assert!(output[cut + 200 * 2..].iter().all(|&value| value == 0.0));
+}
+
+#[test]
+fn new_notes_crossfade_when_declicking() {
+    let cells = [(0, 0, cell(1, C2, 0, 0)), (1, 0, cell(1, C2, 0, 0))];
+    let around_trigger = |declick| {
+        let output = render_ticks(&mut play(&cells, declick), 7);
+        largest_step(&output[(6 * TICK - 100) * 2..(6 * TICK + 100) * 2])
+    };
+    let hard = around_trigger(false);
+    let soft = around_trigger(true);
+    assert!(hard > 0.1);
+    assert!(soft < hard / 10.0);
+}
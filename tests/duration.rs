@@ -0,0 +1,84 @@
+mod common;
+
+use common::{cell, module, player, C2, TICK};
+use protracktor::{LoopMode, Player, PlayerConfig};
+use std::fs;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn duration_ends_where_the_song_loops() {
+    // 16 rows of six ticks, the last of which jumps back to the start
+    let module = module(&[(15, 0, cell(0, 0, 0xb, 0x00))], &[0; 64], (0, 1));
+    let duration = player(module).duration();
+    assert_eq!(duration.total, Duration::from_millis(96 * 1000 / 50));
+    assert_eq!(duration.positions, vec![Some(Duration::ZERO)]);
+}
+
+#[test]
+fn duration_matches_playback() {
+    // four rows at speed 3 and 125 BPM, then four at 80 BPM before `D00`
+    // breaks back to the start
+    let cells = [
+        (0, 0, cell(1, C2, 0xf, 3)),
+        (4, 0, cell(0, 0, 0xf, 80)),
+        (7, 0, cell(0, 0, 0xd, 0)),
+    ];
+    let module = module(&cells, &[64; 64], (0, 32));
+    let config = PlayerConfig {
+        loop_mode: LoopMode::StopAtEnd,
+        ..PlayerConfig::default()
+    };
+    let mut player = Player::with_config(Arc::new(module), config);
+    let frames = 4 * 3 * TICK + 4 * 3 * 1500;
+    assert_eq!(
+        player.duration().total,
+        Duration::from_secs_f64(frames as f64 / 48000.0)
+    );
+
+    let mut buf = vec![0.0; 2 * (frames - 1)];
+    player.render(&mut buf);
+    assert!(!player.is_finished());
+    player.render(&mut [0.0; 2]);
+    assert!(player.is_finished());
+}
+
+#[test]
+fn render_reports_the_frames_played_before_the_end() {
+    // F00 on row 2 stops the song after 13 ticks
+    let module = module(&[(2, 0, cell(0, 0, 0xf, 0x00))], &[0; 64], (0, 1));
+    let mut player = player(module);
+    let mut buf = vec![1.0; 2 * 14 * TICK];
+    assert_eq!(player.render(&mut buf), 13 * TICK);
+    assert!(buf[2 * 13 * TICK..].iter().all(|&value| value == 0.0));
+    assert_eq!(player.render(&mut buf), 0);
+}
+
+#[test]
+fn cli_render_stops_where_the_song_ends() {
+    let dir = std::env::temp_dir().join(format!("protracktor-duration-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    // ends partway through one of the CLI's 1024 frame blocks
+    let cells = [(0, 0, cell(1, C2, 0, 0)), (2, 0, cell(0, 0, 0xf, 0x00))];
+    let module = module(&cells, &[100; 64], (0, 32));
+    let frames = 13 * TICK;
+    assert_ne!(frames % 1024, 0);
+    fs::write(dir.join("song.mod"), module.save()).unwrap();
+    assert_eq!(
+        player(module).duration().total,
+        Duration::from_secs_f64(frames as f64 / 48000.0)
+    );
+
+    let status = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .arg("render")
+        .arg(dir.join("song.mod"))
+        .arg(dir.join("song.wav"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let wav = fs::read(dir.join("song.wav")).unwrap();
+    // stereo 16 bit
+    assert_eq!(wav.len() - 44, frames * 4);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{cell, is_silent, module, player, render_ticks};
+use protracktor::{Event, Module, Note, Sample};
+
+fn empty_module() -> Module {
+    module(&[], &[100; 64], (0, 32))
+}
+
+fn c2(sample: usize) -> Event {
+    Event::new(Note::new(25), Some(sample), 0, 0).unwrap()
+}
+
+#[test]
+fn set_cell_adds_notes_that_play() {
+    let mut module = empty_module();
+    assert!(is_silent(&render_ticks(&mut player(empty_module()), 1)));
+    assert!(module.set_cell(0, 0, 0, c2(1)));
+    assert_eq!(
+        module.patterns()[0].row(0).unwrap().cell(0).unwrap().note(),
+        Note::new(25)
+    );
+    assert!(!is_silent(&render_ticks(&mut player(module), 1)));
+}
+
+#[test]
+fn set_cell_rejects_missing_cells_and_samples() {
+    let mut module = empty_module();
+    assert!(!module.set_cell(1, 0, 0, c2(1)));
+    assert!(!module.set_cell(0, 64, 0, c2(1)));
+    assert!(!module.set_cell(0, 0, 4, c2(1)));
+    assert!(module.set_cell(0, 0, 0, c2(31)));
+    assert!(Event::new(None, Some(32), 0, 0).is_none());
+    assert!(Event::new(None, None, 0x10, 0).is_none());
+    assert!(Note::new(0).is_none() && Note::new(61).is_none());
+}
+
+#[test]
+fn orders_only_point_to_existing_patterns() {
+    let mut module = empty_module();
+    assert!(!module.set_order(1, 1));
+    assert!(!module.set_order(128, 0));
+    assert_eq!(module.add_pattern(), Some(1));
+    assert!(module.set_order(1, 1));
+    assert!(module.set_position_count(2));
+    assert!(!module.set_position_count(0));
+    assert!(!module.set_position_count(129));
+    assert_eq!(
+        module.orders().map(|(index, _)| index).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+
+    let mut player = player(module);
+    render_ticks(&mut player, 64 * 6 + 1);
+    assert_eq!(player.position(), (1, 0));
+}
+
+#[test]
+fn shortened_samples_play_without_panicking() {
+    let mut module = module(&[(0, 0, cell(1, 428, 0, 0))], &[100; 64], (0, 32));
+    let sample = module.sample_mut(0).unwrap();
+    sample.set_data(vec![50; 11]);
+    assert_eq!(sample.length(), 5);
+    assert_eq!(sample.data().len(), 10);
+    assert!(!is_silent(&render_ticks(&mut player(module), 6)));
+}
+
+#[test]
+fn replaced_samples_keep_data_and_length_together() {
+    let mut module = empty_module();
+    let header = Sample::new("kick", vec![1; 64]).save();
+    assert!(module.set_sample(0, Sample::load(&header)));
+    assert_eq!(module.samples()[0].data(), &[0; 64][..]);
+    assert!(!module.set_sample(31, Sample::new("", vec![])));
+
+    let saved = module.save();
+    let loaded = Module::load(&saved).unwrap();
+    assert_eq!(loaded.samples()[0].name.trim_end_matches('\0'), "kick");
+    assert_eq!(loaded.samples()[0].length(), 32);
+}
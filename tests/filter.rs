@@ -0,0 +1,69 @@
+mod common;
+
+use common::{cell, module, render_ticks, C3};
+use protracktor::{AmigaModel, Player, PlayerConfig};
+use std::sync::Arc;
+
+/// C-3 on a square wave, with `E0x` set to `led` on channel 1 if given.
+fn play(led: Option<u8>, amiga_model: Option<AmigaModel>) -> Player {
+    let square: Vec<i8> = (0..64)
+        .map(|i| if i % 8 < 4 { 100 } else { -100 })
+        .collect();
+    let mut cells = vec![(0, 0, cell(1, C3, 0, 0))];
+    if let Some(led) = led {
+        cells.push((0, 1, cell(0, 0, 0xe, led)));
+    }
+    let config = PlayerConfig {
+        amiga_model,
+        ..PlayerConfig::default()
+    };
+    Player::with_config(Arc::new(module(&cells, &square, (0, 32))), config)
+}
+
+#[test]
+fn e0x_switches_the_led_filter() {
+    let cells = [(0, 0, cell(0, 0, 0xe, 0x00)), (1, 0, cell(0, 0, 0xe, 0x01))];
+    let mut player = common::player(module(&cells, &[0; 64], (0, 1)));
+    assert!(!player.led_filter());
+    render_ticks(&mut player, 1);
+    assert!(player.led_filter());
+    render_ticks(&mut player, 6);
+    assert!(!player.led_filter());
+}
+
+#[test]
+fn led_filter_is_only_heard_on_an_amiga_model() {
+    let output = |led, amiga_model| render_ticks(&mut play(led, amiga_model), 6);
+    assert!(output(Some(0x00), None) == output(None, None));
+    for model in [AmigaModel::A500, AmigaModel::A1200] {
+        let (on, off) = (output(Some(0x00), Some(model)), output(None, Some(model)));
+        assert!(on != off, "{model:?}");
+        assert!(output(Some(0x01), Some(model)) == off, "{model:?}");
+    }
+}
+
+#[test]
+fn amiga_models_filter_differently() {
+    let output = |amiga_model| render_ticks(&mut play(None, amiga_model), 6);
+    let (unfiltered, a500, a1200) = (
+        output(None),
+        output(Some(AmigaModel::A500)),
+        output(Some(AmigaModel::A1200)),
+    );
+    assert!(a500 != unfiltered);
+    assert!(a1200 != unfiltered);
+    assert!(a500 != a1200);
+
+    // the A500's 4.4 kHz low-pass softens the square wave edges most
+    let roughness = |output: &[f32]| -> f32 {
+        output
+            .chunks(2)
+            .map(|frame| frame[0])
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .sum()
+    };
+    assert!(roughness(&a500) < roughness(&a1200));
+    assert!(roughness(&a500) < roughness(&unfiltered));
+}
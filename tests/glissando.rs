@@ -0,0 +1,47 @@
+mod common;
+
+use common::{cell, module, player, render_ticks, C2, C3};
+
+/// Periods of channel 0 on the five effect ticks of a `310` slide from C-2
+/// up to C-3, with `E3x` set to `glissando` on the row before.
+fn slide_periods(glissando: u8) -> Vec<usize> {
+    let module = module(
+        &[
+            (0, 0, cell(1, C2, 0xe, 0x30 | glissando)),
+            (1, 0, cell(0, C3, 3, 0x10)),
+        ],
+        &[0; 64],
+        (0, 1),
+    );
+    let mut player = player(module);
+    render_ticks(&mut player, 7);
+    (0..5)
+        .map(|_| {
+            render_ticks(&mut player, 1);
+            player.channel_period(0)
+        })
+        .collect()
+}
+
+#[test]
+fn tone_portamento_slides_smoothly_without_glissando() {
+    assert_eq!(slide_periods(0), vec![412, 396, 380, 364, 348]);
+}
+
+#[test]
+fn glissando_snaps_tone_portamento_to_semitones() {
+    // C#2, D-2, D#2, D#2, E-2
+    assert_eq!(slide_periods(1), vec![404, 381, 360, 360, 339]);
+}
+
+#[test]
+fn glissando_reaches_the_target_note() {
+    let module = module(
+        &[(0, 0, cell(1, C2, 0xe, 0x31)), (1, 0, cell(0, C3, 3, 0xff))],
+        &[0; 64],
+        (0, 1),
+    );
+    let mut player = player(module);
+    render_ticks(&mut player, 8);
+    assert_eq!(player.channel_period(0), C3 as usize);
+}
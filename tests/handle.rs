@@ -0,0 +1,102 @@
+mod common;
+
+use common::{cell, is_silent, module, player, C2, TICK};
+use protracktor::{Command, PlayerHandle, PlayerRenderer};
+use std::thread;
+
+fn split() -> (PlayerHandle, PlayerRenderer) {
+    player(module(&[(0, 0, cell(1, C2, 0, 0))], &[100; 64], (0, 32))).split()
+}
+
+fn render_tick(renderer: &mut PlayerRenderer) -> Vec<f32> {
+    let mut buf = vec![0.0; TICK * 2];
+    renderer.render(&mut buf);
+    buf
+}
+
+#[test]
+fn commands_reach_the_renderer() {
+    let (mut handle, mut renderer) = split();
+    assert!(!is_silent(&render_tick(&mut renderer)));
+
+    handle.send(Command::SetChannelMuted(0, true)).unwrap();
+    assert!(is_silent(&render_tick(&mut renderer)));
+    assert!(renderer.player().is_channel_muted(0));
+}
+
+#[test]
+fn paused_renderer_holds_its_position() {
+    let (mut handle, mut renderer) = split();
+    handle.send(Command::Pause).unwrap();
+    for _ in 0..12 {
+        assert!(is_silent(&render_tick(&mut renderer)));
+    }
+    let state = handle.state();
+    assert!(state.paused);
+    assert_eq!(state.position, (0, 0));
+
+    handle.send(Command::Resume).unwrap();
+    assert!(!is_silent(&render_tick(&mut renderer)));
+}
+
+#[test]
+fn state_reports_position_and_levels() {
+    let (mut handle, mut renderer) = split();
+    // the first block plays tick 0, the seventh starts row 1
+    for _ in 0..7 {
+        render_tick(&mut renderer);
+    }
+    let state = handle.state();
+    assert_eq!(state.position, (0, 1));
+    assert_eq!(state.levels().len(), 4);
+    assert!(state.levels()[0] > 0.5);
+    assert_eq!(state.levels()[1], 0.0);
+}
+
+#[test]
+fn state_stays_current_when_the_handle_polls_rarely() {
+    let (mut handle, mut renderer) = split();
+    // far more blocks than a queue of states would hold
+    for _ in 0..500 {
+        render_tick(&mut renderer);
+    }
+    assert_eq!(handle.state().position, renderer.player().position());
+    render_tick(&mut renderer);
+    assert_eq!(handle.state().position, renderer.player().position());
+}
+
+#[test]
+fn full_command_queue_hands_commands_back() {
+    let (mut handle, _renderer) = split();
+    let sent = (0..1000)
+        .take_while(|_| handle.send(Command::Pause).is_ok())
+        .count();
+    assert!((64..1000).contains(&sent));
+    assert_eq!(handle.send(Command::Resume), Err(Command::Resume));
+}
+
+#[test]
+fn renderer_runs_on_another_thread() {
+    let (mut handle, mut renderer) = split();
+    handle
+        .send(Command::SeekToOrder { order: 0, row: 32 })
+        .unwrap();
+    thread::spawn(move || {
+        render_tick(&mut renderer);
+    })
+    .join()
+    .unwrap();
+    assert_eq!(handle.state().position, (0, 32));
+}
+
+#[test]
+fn commands_for_missing_channels_are_ignored() {
+    let (mut handle, mut renderer) = split();
+    handle.send(Command::SetChannelMuted(4, true)).unwrap();
+    handle.send(Command::SetChannelSolo(99, true)).unwrap();
+    handle
+        .send(Command::SetChannelGain(usize::MAX, 0.0))
+        .unwrap();
+    handle.send(Command::SetChannelPan(4, 1.0)).unwrap();
+    assert!(!is_silent(&render_tick(&mut renderer)));
+}
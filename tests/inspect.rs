@@ -0,0 +1,82 @@
+mod common;
+
+use common::{cell, module, C2};
+use protracktor::Note;
+
+#[test]
+fn notes_display_like_protracker() {
+    let names: Vec<String> = [1, 2, 13, 25, 34, 60]
+        .into_iter()
+        .map(|index| Note::new(index).unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["C-0", "C#0", "C-1", "C-2", "A-2", "B-4"]);
+    assert_eq!(Note::new(25).unwrap().period(), C2);
+    assert_eq!(Note::new(60).unwrap().octave(), 4);
+    assert_eq!(Note::new(0), None);
+    assert_eq!(Note::new(61), None);
+}
+
+#[test]
+fn cells_keep_periods_off_the_table() {
+    let module = module(
+        &[
+            (0, 0, cell(1, C2, 0, 0)),
+            (0, 1, cell(1, C2 + 2, 0, 0)),
+            (0, 2, cell(1, 0, 0xc, 0x20)),
+        ],
+        &[0; 64],
+        (0, 1),
+    );
+    let row = module.patterns()[0].row(0).unwrap();
+
+    let on_table = row.cell(0).unwrap();
+    assert_eq!(on_table.note().unwrap().to_string(), "C-2");
+    assert_eq!(on_table.raw_period(), None);
+
+    // snapped to the nearest note, with the period kept for saving
+    let off_table = row.cell(1).unwrap();
+    assert_eq!(off_table.note().unwrap().to_string(), "C-2");
+    assert_eq!(off_table.raw_period(), Some(C2 + 2));
+
+    let no_note = row.cell(2).unwrap();
+    assert_eq!(no_note.note(), None);
+    assert_eq!(no_note.raw_period(), None);
+    assert_eq!(no_note.sample(), Some(1));
+    assert_eq!((no_note.effect(), no_note.effect_param()), (0xc, 0x20));
+}
+
+#[test]
+fn patterns_iterate_over_rows_and_cells() {
+    let module = module(&[(2, 3, cell(1, C2, 0, 0))], &[0; 64], (0, 1));
+    let pattern = &module.patterns()[0];
+    assert_eq!(pattern.row_count(), 64);
+    assert_eq!(pattern.rows().count(), 64);
+    assert!(pattern.rows().all(|row| row.cells().count() == 4));
+
+    let notes: Vec<(usize, usize)> = pattern
+        .rows()
+        .enumerate()
+        .flat_map(|(index, row)| {
+            row.cells()
+                .enumerate()
+                .filter(|(_, cell)| cell.note().is_some())
+                .map(move |(channel, _)| (index, channel))
+        })
+        .collect();
+    assert_eq!(notes, [(2, 3)]);
+    assert!(pattern.row(64).is_none());
+    assert!(pattern.row(0).unwrap().cell(4).is_none());
+}
+
+#[test]
+fn orders_follow_the_order_list() {
+    let mut module = module(&[(0, 0, cell(1, C2, 0, 0))], &[0; 64], (0, 1));
+    let added = module.add_pattern().unwrap();
+    assert!(module.set_order(1, added));
+    assert!(module.set_position_count(3));
+
+    let orders: Vec<usize> = module.orders().map(|(number, _)| number).collect();
+    assert_eq!(orders, [0, added, 0]);
+    let (_, first) = module.orders().next().unwrap();
+    assert!(first.row(0).unwrap().cell(0).unwrap().note().is_some());
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::{cell, module_with_samples, render_ticks, TICK};
+use protracktor::{Interpolation, Player, PlayerConfig};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// C-1 on a sample of 64 bytes of noise followed by one cosine cycle of 64
+/// bytes, which is the loop, played with `interpolation`. Returns the left
+/// side of six ticks.
+fn play_cosine_loop(interpolation: Interpolation) -> Vec<f32> {
+    let mut sample: Vec<i8> = (0..64)
+        .map(|i| if i % 2 == 0 { 127 } else { -128 })
+        .collect();
+    sample.extend((0..64).map(|i| ((i as f32 / 64.0 * 2.0 * PI).cos() * 100.0) as i8));
+    let module = module_with_samples(&[(0, 0, cell(1, 856, 0, 0))], &[(&sample, (32, 32))]);
+    let config = PlayerConfig {
+        interpolation,
+        ..PlayerConfig::default()
+    };
+    let mut player = Player::with_config(Arc::new(module), config);
+    render_ticks(&mut player, 6)
+        .into_iter()
+        .step_by(2)
+        .collect()
+}
+
+/// Largest change between two output samples from the third tick on, when
+/// the voice has long left the noise and wraps around the loop every 745
+/// samples.
+fn largest_step(output: &[f32]) -> f32 {
+    output[2 * TICK..]
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn interpolating_kernels_wrap_around_the_loop_smoothly() {
+    // a cosine of this level and pitch moves by less than 0.002 per sample,
+    // anything picking up the noise or silence at the loop ends jumps
+    for interpolation in [
+        Interpolation::Linear,
+        Interpolation::Hermite,
+        Interpolation::Sinc,
+    ] {
+        let step = largest_step(&play_cosine_loop(interpolation));
+        assert!(step < 0.005, "{interpolation:?}: {step}");
+    }
+}
+
+#[test]
+fn nearest_holds_each_byte() {
+    let output = play_cosine_loop(Interpolation::Nearest);
+    // held bytes change by at most one cosine step of the loop
+    assert!(largest_step(&output) < 0.05);
+    // and stay put for the 11 or 12 samples it takes to fetch the next one
+    let changes = output[2 * TICK..]
+        .windows(2)
+        .filter(|pair| pair[0] != pair[1])
+        .count();
+    let expected = (4 * TICK) as f32 * 3546895.0 / (856.0 * 48000.0);
+    assert!((changes as f32 - expected).abs() < 4.0, "{changes}");
+}
+
+#[test]
+fn interpolation_modes_differ() {
+    let linear = play_cosine_loop(Interpolation::Linear);
+    for interpolation in [
+        Interpolation::Nearest,
+        Interpolation::Hermite,
+        Interpolation::Sinc,
+    ] {
+        assert!(
+            play_cosine_loop(interpolation) != linear,
+            "{interpolation:?}"
+        );
+    }
+}
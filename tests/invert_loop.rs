@@ -0,0 +1,68 @@
+mod common;
+
+use common::{cell, module, player, render_ticks, C2};
+
+fn inverted(data: &[i8]) -> Vec<usize> {
+    (0..data.len())
+        .filter(|&index| data[index] == -11)
+        .collect()
+}
+
+#[test]
+fn invert_loop_flips_loop_bytes_at_full_speed() {
+    // EFF inverts one byte every tick, starting after the loop start
+    let module = module(&[(0, 0, cell(1, C2, 0xe, 0xff))], &[10; 32], (4, 8));
+    let mut player = player(module);
+    render_ticks(&mut player, 6);
+    assert_eq!(inverted(player.sample_data(0)), vec![9, 10, 11, 12, 13, 14]);
+}
+
+#[test]
+fn invert_loop_wraps_around_at_the_loop_end() {
+    let module = module(&[(0, 0, cell(1, C2, 0xe, 0xff))], &[10; 8], (1, 2));
+    let mut player = player(module);
+    render_ticks(&mut player, 5);
+    // bytes 3, 4, 5 and 2 once each, then 3 again back to its original
+    assert_eq!(inverted(player.sample_data(0)), vec![2, 4, 5]);
+}
+
+#[test]
+fn invert_loop_speed_follows_the_funk_table() {
+    // EF8 adds 16 per tick, so only every eighth tick inverts a byte
+    let cells: Vec<_> = (0..4).map(|row| (row, 0, cell(1, C2, 0xe, 0xf8))).collect();
+    let module = module(&cells, &[10; 32], (0, 16));
+    let mut player = player(module);
+    render_ticks(&mut player, 7);
+    assert!(inverted(player.sample_data(0)).is_empty());
+    render_ticks(&mut player, 1);
+    assert_eq!(inverted(player.sample_data(0)), vec![1]);
+}
+
+#[test]
+fn invert_loop_stops_with_ef0() {
+    let module = module(
+        &[
+            (0, 0, cell(1, C2, 0xe, 0xff)),
+            (1, 0, cell(0, 0, 0xe, 0xf0)),
+        ],
+        &[10; 32],
+        (0, 16),
+    );
+    let mut player = player(module);
+    render_ticks(&mut player, 12);
+    assert_eq!(inverted(player.sample_data(0)).len(), 6);
+}
+
+#[test]
+fn invert_loop_leaves_the_module_untouched() {
+    let module = module(&[(0, 0, cell(1, C2, 0xe, 0xff))], &[10; 32], (0, 16));
+    let mut player = player(module);
+    render_ticks(&mut player, 6);
+    assert!(player.module().samples()[0]
+        .data()
+        .iter()
+        .all(|&byte| byte == 10));
+
+    player.seek_to_order(0, 0);
+    assert!(inverted(player.sample_data(0)).is_empty());
+}
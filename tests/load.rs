@@ -0,0 +1,87 @@
+use protracktor::{LoadError, Module};
+use std::fs;
+
+/// `test/hbt.chip-munch.mod`: 40 patterns, then the data of 11 samples, of
+/// which the last two take 912 and 20 bytes.
+fn chip_munch() -> Vec<u8> {
+    fs::read("test/hbt.chip-munch.mod").unwrap()
+}
+
+const PATTERN_DATA: usize = 1084;
+
+#[test]
+fn load_rejects_files_shorter_than_a_header() {
+    let original = chip_munch();
+    assert_eq!(
+        Module::load(&original[..599]).err(),
+        Some(LoadError::TooShort {
+            length: 599,
+            required: 600
+        })
+    );
+}
+
+#[test]
+fn load_rejects_bad_song_lengths() {
+    let mut original = chip_munch();
+    for length in [0, 129] {
+        original[950] = length;
+        assert!(matches!(
+            Module::load(&original),
+            Err(LoadError::BadHeader(_))
+        ));
+    }
+}
+
+#[test]
+fn load_rejects_patterns_past_the_end() {
+    let original = chip_munch();
+    let cut = &original[..PATTERN_DATA + 10 * 1024 + 5];
+    assert_eq!(
+        Module::load(cut).err(),
+        Some(LoadError::PatternDataPastEof { pattern: 10 })
+    );
+    // lenient loading only forgives sample data
+    assert_eq!(
+        Module::load_lenient(cut).err(),
+        Some(LoadError::PatternDataPastEof { pattern: 10 })
+    );
+}
+
+#[test]
+fn load_rejects_samples_past_the_end() {
+    let original = chip_munch();
+    assert_eq!(
+        Module::load(&original[..original.len() - 10]).err(),
+        Some(LoadError::SampleDataPastEof {
+            sample: 10,
+            missing: 10
+        })
+    );
+    assert_eq!(
+        Module::load(&original[..original.len() - 100]).err(),
+        Some(LoadError::SampleDataPastEof {
+            sample: 9,
+            missing: 80
+        })
+    );
+}
+
+#[test]
+fn load_lenient_zero_pads_cut_off_samples() {
+    let original = chip_munch();
+    let complete = Module::load(&original).unwrap();
+    let module = Module::load_lenient(&original[..original.len() - 100]).unwrap();
+
+    let (cut, full) = (module.samples()[9].data(), complete.samples()[9].data());
+    assert_eq!(cut.len(), 912);
+    assert_eq!(cut[..832], full[..832]);
+    assert!(cut[832..].iter().all(|&byte| byte == 0));
+    assert_eq!(module.samples()[10].data(), &[0; 20][..]);
+    for index in 0..9 {
+        assert_eq!(
+            module.samples()[index].data(),
+            complete.samples()[index].data()
+        );
+    }
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{cell, module, player, render_ticks, C2, C3};
+use protracktor::Player;
+
+/// A ramp, so that output depends on where in the sample a voice is.
+fn ramp() -> Vec<i8> {
+    (0..64).map(|i| (i * 4 - 128) as i8).collect()
+}
+
+/// Channel 0 plays C-2 and channel 1 C-3 unless left out by `channels`.
+fn playing(channels: &[usize]) -> Player {
+    let cells: Vec<_> = [(0, 0, cell(1, C2, 0, 0)), (0, 1, cell(1, C3, 0, 0))]
+        .into_iter()
+        .filter(|(_, channel, _)| channels.contains(channel))
+        .collect();
+    player(module(&cells, &ramp(), (0, 32)))
+}
+
+#[test]
+fn muted_channels_leave_the_mix() {
+    let mut player = playing(&[0, 1]);
+    player.set_channel_muted(0, true);
+    assert!(player.is_channel_muted(0));
+    assert!(!player.is_channel_audible(0));
+    assert!(render_ticks(&mut player, 6) == render_ticks(&mut playing(&[1]), 6));
+}
+
+#[test]
+fn unmuted_channels_come_back_in_sync() {
+    let mut player = playing(&[0, 1]);
+    let mut uninterrupted = playing(&[0, 1]);
+    player.set_channel_muted(0, true);
+    render_ticks(&mut player, 5);
+    render_ticks(&mut uninterrupted, 5);
+    player.set_channel_muted(0, false);
+    assert!(render_ticks(&mut player, 6) == render_ticks(&mut uninterrupted, 6));
+}
+
+#[test]
+fn solo_silences_the_other_channels() {
+    let mut player = playing(&[0, 1]);
+    player.set_channel_solo(1, true);
+    // soloing overrides muting
+    player.set_channel_muted(1, true);
+    assert!(player.is_channel_solo(1));
+    assert!(player.is_channel_audible(1));
+    assert!(!player.is_channel_audible(0));
+    assert!(render_ticks(&mut player, 6) == render_ticks(&mut playing(&[1]), 6));
+
+    player.set_channel_solo(1, false);
+    player.set_channel_muted(1, false);
+    let mut both = playing(&[0, 1]);
+    render_ticks(&mut both, 6);
+    assert!(render_ticks(&mut player, 6) == render_ticks(&mut both, 6));
+}
+
+#[test]
+fn gain_scales_a_channel() {
+    let mut player = playing(&[0]);
+    player.set_channel_gain(0, 0.5);
+    assert_eq!(player.channel_gain(0), 0.5);
+    let full = render_ticks(&mut playing(&[0]), 6);
+    let half = render_ticks(&mut player, 6);
+    assert!(full.iter().any(|&value| value != 0.0));
+    for (half, full) in half.iter().zip(full.iter()) {
+        assert!((half - full * 0.5).abs() < 1e-6);
+    }
+}
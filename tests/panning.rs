@@ -0,0 +1,123 @@
+mod common;
+
+use common::{cell, is_silent, module, player, render_ticks, C2};
+use protracktor::{ChannelLayout, Player, PlayerConfig};
+use std::sync::Arc;
+
+/// C-2 on channel `ch`, which starts out on the left for 0 and 3.
+fn playing_on(ch: usize) -> Player {
+    player(module(&[(0, ch, cell(1, C2, 0, 0))], &[100; 64], (0, 32)))
+}
+
+/// Left and right sides of one tick.
+fn sides(player: &mut Player) -> (Vec<f32>, Vec<f32>) {
+    let output = render_ticks(player, 1);
+    let left = output.iter().step_by(2).copied().collect();
+    let right = output.iter().skip(1).step_by(2).copied().collect();
+    (left, right)
+}
+
+#[test]
+fn full_separation_keeps_channels_on_their_side() {
+    let mut player = playing_on(0);
+    player.set_stereo_separation(1.0);
+    let (left, right) = sides(&mut player);
+    assert!(!is_silent(&left));
+    assert!(is_silent(&right));
+}
+
+#[test]
+fn no_separation_centres_every_channel() {
+    let mut player = playing_on(1);
+    player.set_stereo_separation(0.0);
+    let (left, right) = sides(&mut player);
+    assert!(!is_silent(&left));
+    assert!(left == right);
+}
+
+#[test]
+fn default_separation_blends_a_quarter() {
+    let mut player = playing_on(0);
+    assert_eq!(player.stereo_separation(), 0.25);
+    let (left, right) = sides(&mut player);
+    for (left, right) in left.iter().zip(right.iter()) {
+        assert!((left * 3.0 - right * 5.0).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn separation_and_pan_are_clamped() {
+    let mut player = playing_on(0);
+    player.set_stereo_separation(2.0);
+    assert_eq!(player.stereo_separation(), 1.0);
+    player.set_channel_pan(0, -3.0);
+    assert_eq!(player.channel_pan(0), -1.0);
+}
+
+#[test]
+fn channel_pan_moves_a_channel() {
+    let mut player = playing_on(0);
+    player.set_stereo_separation(1.0);
+    player.set_channel_pan(0, 1.0);
+    let (left, right) = sides(&mut player);
+    assert!(is_silent(&left));
+    assert!(!is_silent(&right));
+
+    // centred, both sides get half
+    let mut player = playing_on(0);
+    player.set_stereo_separation(1.0);
+    player.set_channel_pan(0, 0.0);
+    let (left, right) = sides(&mut player);
+    assert!(left == right);
+}
+
+#[test]
+fn mono_output_matches_a_centred_mix() {
+    let mut centred = playing_on(0);
+    centred.set_stereo_separation(0.0);
+    let (left, _) = sides(&mut centred);
+
+    let module = module(&[(0, 0, cell(1, C2, 0, 0))], &[100; 64], (0, 32));
+    let config = PlayerConfig {
+        channel_layout: ChannelLayout::Mono,
+        ..PlayerConfig::default()
+    };
+    let mut player = Player::with_config(Arc::new(module), config);
+    let mut mono = vec![0.0; left.len()];
+    player.render(&mut mono);
+    assert!(mono == left);
+    // a tick of mono frames is a full tick
+    assert_eq!(player.position(), (0, 0));
+    player.render(&mut vec![0.0; 5 * left.len()]);
+    assert_eq!(player.position(), (0, 1));
+}
+
+/// Full separation on `cell`, with extended panning on or off.
+fn panned(cell: [u8; 4], extended_panning: bool) -> Player {
+    let module = module(&[(0, 0, cell)], &[100; 64], (0, 32));
+    let config = PlayerConfig {
+        extended_panning,
+        ..PlayerConfig::default()
+    };
+    let mut player = Player::with_config(Arc::new(module), config);
+    player.set_stereo_separation(1.0);
+    player
+}
+
+#[test]
+fn pan_effects_move_a_channel_in_extended_panning_mode() {
+    for cell in [cell(1, C2, 8, 0xff), cell(1, C2, 0xe, 0x8f)] {
+        let (left, right) = sides(&mut panned(cell, true));
+        assert!(is_silent(&left), "{cell:02x?}");
+        assert!(!is_silent(&right), "{cell:02x?}");
+    }
+}
+
+#[test]
+fn pan_effects_are_ignored_by_default() {
+    for cell in [cell(1, C2, 8, 0xff), cell(1, C2, 0xe, 0x8f)] {
+        let (left, right) = sides(&mut panned(cell, false));
+        assert!(!is_silent(&left), "{cell:02x?}");
+        assert!(is_silent(&right), "{cell:02x?}");
+    }
+}
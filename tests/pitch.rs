@@ -0,0 +1,34 @@
+mod common;
+
+use common::{cell, module, player, render_ticks, C2, C3};
+
+/// Cycles per second channel 0 plays at with a note of `period`, counted
+/// over one second of a square wave sample with one cycle every 32 bytes.
+fn played_frequency(period: u16) -> usize {
+    let wave: Vec<i8> = (0..32).map(|i| if i < 16 { 100 } else { -100 }).collect();
+    let module = module(&[(0, 0, cell(1, period, 0, 0))], &wave, (0, 16));
+    let mut player = player(module);
+    // one second at 50 ticks per second
+    let out = render_ticks(&mut player, 50);
+    let left: Vec<f32> = out.iter().step_by(2).copied().collect();
+    left.windows(2)
+        .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+        .count()
+}
+
+#[test]
+fn c2_plays_at_the_pal_paula_rate() {
+    // 3546895 Hz / 428 / 32 is 259 Hz, a semitone down would be 244 Hz
+    let hz = played_frequency(C2);
+    assert!((257..=261).contains(&hz), "{hz} Hz");
+}
+
+#[test]
+fn notes_play_at_their_own_period() {
+    // C-1, C-3 and B-3
+    for period in [856, C3, 113] {
+        let expected = 3546895 / (period as usize * 32);
+        let hz = played_frequency(period);
+        assert!(hz.abs_diff(expected) <= 2, "{period}: {hz} Hz");
+    }
+}
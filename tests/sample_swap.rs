@@ -0,0 +1,60 @@
+mod common;
+
+use common::{cell, module, module_with_samples, player, render_ticks, C2, C3, TICK};
+
+/// Left channel of interleaved stereo output.
+fn left(output: &[f32]) -> Vec<f32> {
+    output.iter().step_by(2).copied().collect()
+}
+
+#[test]
+fn one_shot_samples_end_in_silence() {
+    // 64 bytes at C-3 are over after about 190 frames
+    let module = module(&[(0, 0, cell(1, C3, 0, 0))], &[100; 64], (0, 1));
+    let mut player = player(module);
+    let output = left(&render_ticks(&mut player, 1));
+    assert!(output[..150].iter().all(|&value| value > 0.0));
+    assert!(output[250..].iter().all(|&value| value == 0.0));
+}
+
+#[test]
+fn sample_without_note_swaps_at_the_loop_end() {
+    let high: &[i8] = &[100; 64];
+    let low: &[i8] = &[-100; 64];
+    let module = module_with_samples(
+        &[(0, 0, cell(1, C2, 0, 0)), (1, 0, cell(2, 0, 0, 0))],
+        &[(high, (0, 32)), (low, (0, 32))],
+    );
+    let mut player = player(module);
+    let output = left(&render_ticks(&mut player, 8));
+
+    // the loop of the first sample is about 30 bytes from its end on row 1
+    let row = 6 * TICK;
+    assert!(output[row..row + 100].iter().all(|&value| value > 0.0));
+    assert!(output[row + 250..].iter().all(|&value| value < 0.0));
+}
+
+#[test]
+fn sample_with_note_swaps_at_once() {
+    let high: &[i8] = &[100; 64];
+    let low: &[i8] = &[-100; 64];
+    let module = module_with_samples(
+        &[(0, 0, cell(1, C2, 0, 0)), (1, 0, cell(2, C2, 0, 0))],
+        &[(high, (0, 32)), (low, (0, 32))],
+    );
+    let mut player = player(module);
+    let output = left(&render_ticks(&mut player, 8));
+    assert!(output[6 * TICK + 10..].iter().all(|&value| value < 0.0));
+}
+
+#[test]
+fn sample_number_without_any_note_stays_silent() {
+    // retrigger and note delay with nothing to retrigger used to leave the
+    // voice without a period
+    for effect in [0x93, 0xd2] {
+        let module = module(&[(0, 0, cell(1, 0, 0xe, effect))], &[100; 64], (0, 32));
+        let mut player = player(module);
+        let output = render_ticks(&mut player, 12);
+        assert!(output.iter().all(|&value| value == 0.0));
+    }
+}
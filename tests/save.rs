@@ -0,0 +1,65 @@
+use protracktor::Module;
+use std::fs;
+
+#[test]
+fn save_round_trips_protracker_module() {
+    let original = fs::read("test/hbt.chip-munch.mod").unwrap();
+    let module = Module::load(&original).unwrap();
+    assert!(module.save() == original);
+}
+
+#[test]
+fn save_keeps_the_loaded_tag() {
+    let mut original = fs::read("test/hbt.chip-munch.mod").unwrap();
+    for tag in [b"M!K!", b"FLT4", b"4TLF", b"N.T."] {
+        original[1080..1084].copy_from_slice(tag);
+        let module = Module::load(&original).unwrap();
+        assert!(
+            module.save() == original,
+            "{}",
+            String::from_utf8_lossy(tag)
+        );
+    }
+}
+
+#[test]
+fn save_switches_tag_above_64_patterns() {
+    let original = fs::read("test/hbt.chip-munch.mod").unwrap();
+    let mut module = Module::load(&original).unwrap();
+    while module.patterns().len() <= 64 {
+        module.add_pattern().unwrap();
+    }
+    // patterns the order list does not reach are not saved
+    assert!(module.set_order(127, 64));
+    let saved = module.save();
+    assert_eq!(&saved[1080..1084], b"M!K!");
+    assert_eq!(
+        Module::load(&saved).unwrap().patterns().len(),
+        module.patterns().len()
+    );
+}
+
+#[test]
+fn save_writes_the_length_of_edited_samples() {
+    let original = fs::read("test/hbt.chip-munch.mod").unwrap();
+    let mut module = Module::load(&original).unwrap();
+    module.sample_mut(0).unwrap().set_data(vec![1; 100]);
+    let saved = Module::load(&module.save()).unwrap();
+    assert_eq!(saved.samples()[0].length(), 50);
+    assert_eq!(saved.samples()[0].data(), &[1; 100][..]);
+    for (saved, sample) in saved.samples().iter().zip(module.samples()).skip(1) {
+        assert_eq!(saved.data(), sample.data());
+    }
+}
+
+#[test]
+fn save_leaves_out_patterns_the_order_list_does_not_reach() {
+    let original = fs::read("test/hbt.chip-munch.mod").unwrap();
+    let mut module = Module::load(&original).unwrap();
+    module.add_pattern().unwrap();
+    let saved = Module::load(&module.save()).unwrap();
+    assert_eq!(saved.patterns().len(), 40);
+    for (saved, sample) in saved.samples().iter().zip(module.samples()) {
+        assert_eq!(saved.data(), sample.data());
+    }
+}
@@ -0,0 +1,73 @@
+use protracktor::{Module, Player};
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+const RATE: f64 = 48000.0;
+
+fn chip_munch() -> Player {
+    let module = Module::load(&fs::read("test/hbt.chip-munch.mod").unwrap()).unwrap();
+    Player::new(Arc::new(module))
+}
+
+/// Stereo output of the next `frames` frames.
+fn render(player: &mut Player, frames: usize) -> Vec<f32> {
+    let mut buf = vec![0.0; frames * 2];
+    player.render(&mut buf);
+    buf
+}
+
+/// Output of uninterrupted playback from `time` on.
+fn played_from(time: Duration, frames: usize) -> Vec<f32> {
+    let mut player = chip_munch();
+    render(&mut player, (time.as_secs_f64() * RATE).round() as usize);
+    render(&mut player, frames)
+}
+
+#[test]
+fn seek_to_order_continues_like_playback() {
+    let mut player = chip_munch();
+    let time = player.duration().positions[5].unwrap();
+    assert!(player.seek_to_order(5, 0));
+    assert!(render(&mut player, 9600) == played_from(time, 9600));
+}
+
+#[test]
+fn seek_to_time_continues_like_playback() {
+    let mut player = chip_munch();
+    let time = player.duration().positions[12].unwrap();
+    player.seek_to_time(time);
+    assert!(render(&mut player, 9600) == played_from(time, 9600));
+}
+
+#[test]
+fn seeking_back_restarts_the_song() {
+    let mut player = chip_munch();
+    let start = render(&mut player, 9600);
+    render(&mut player, 48000);
+    player.seek_to_time(Duration::ZERO);
+    assert!(render(&mut player, 9600) == start);
+    assert!(player.seek_to_order(0, 0));
+    assert!(render(&mut player, 9600) == start);
+}
+
+#[test]
+fn positions_start_at_their_duration_timestamps() {
+    let mut player = chip_munch();
+    let duration = player.duration();
+    for (position, time) in duration.positions.iter().enumerate().take(8) {
+        if let Some(time) = time {
+            // a frame into the position, whatever the rounding of `time`
+            player.seek_to_time(*time + Duration::from_micros(10));
+            assert_eq!(player.position(), (position, 0));
+        }
+    }
+}
+
+#[test]
+fn seek_to_order_rejects_missing_positions() {
+    let mut player = chip_munch();
+    let positions = player.duration().positions.len();
+    assert!(!player.seek_to_order(positions, 0));
+    assert!(!player.seek_to_order(0, 64));
+}
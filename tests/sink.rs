@@ -0,0 +1,75 @@
+mod common;
+
+use common::{cell, module, player, C2, TICK};
+use protracktor::{AudioSink, ChannelLayout, FileFormat, FileSink, NullSink, Player, WavFormat};
+use std::io::Cursor;
+use std::time::Duration;
+
+fn looping_player() -> Player {
+    player(module(&[(0, 0, cell(1, C2, 0, 0))], &[100; 64], (0, 32)))
+}
+
+#[test]
+fn null_sink_plays_up_to_its_limit() {
+    let mut sink = NullSink::new(22050, ChannelLayout::Mono).with_limit(Duration::from_secs(1));
+    let mut handle = looping_player().play_on(&mut sink).unwrap();
+    assert_eq!(sink.frames(), 22050);
+    // 50 ticks of six per row
+    assert_eq!(handle.state().position, (0, 8));
+}
+
+#[test]
+fn null_sink_stops_at_the_end_of_the_song() {
+    let stopping = module(
+        &[(0, 0, cell(1, C2, 0, 0)), (2, 1, cell(0, 0, 0xf, 0x00))],
+        &[64; 64],
+        (0, 32),
+    );
+    let player = player(stopping);
+    let duration = player.duration().total;
+    let mut sink = NullSink::new(48000, ChannelLayout::Stereo);
+    let mut handle = player.play_on(&mut sink).unwrap();
+    assert!(handle.state().finished);
+    // the last block stops with the song
+    assert_eq!(sink.frames(), (duration.as_secs_f64() * 48000.0) as usize);
+    assert_eq!(sink.frames(), 13 * TICK);
+}
+
+#[test]
+fn raw_file_sink_writes_what_the_player_renders() {
+    let mut sink = FileSink::new(
+        Cursor::new(Vec::new()),
+        FileFormat::Raw(WavFormat::Float32),
+        48000,
+        ChannelLayout::Stereo,
+    )
+    .with_limit(Duration::from_millis(100));
+    looping_player().play_on(&mut sink).unwrap();
+    let bytes = sink.into_inner().unwrap().into_inner();
+
+    let mut expected = vec![0.0; 4800 * 2];
+    looping_player().render(&mut expected);
+    let written: Vec<f32> = bytes
+        .chunks(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn wav_file_sink_writes_a_complete_file() {
+    let mut sink = FileSink::new(
+        Cursor::new(Vec::new()),
+        FileFormat::Wav(WavFormat::Int16),
+        44100,
+        ChannelLayout::Mono,
+    )
+    .with_limit(Duration::from_secs(1));
+    assert_eq!(sink.format(), (44100, ChannelLayout::Mono));
+    looping_player().play_on(&mut sink).unwrap();
+    let bytes = sink.into_inner().unwrap().into_inner();
+
+    assert_eq!(bytes.len(), 44 + 44100 * 2);
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(&bytes[40..44], &(44100u32 * 2).to_le_bytes());
+}
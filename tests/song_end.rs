@@ -0,0 +1,44 @@
+mod common;
+
+use common::{cell, module, render_ticks};
+use protracktor::{LoopMode, Player, PlayerConfig};
+use std::sync::Arc;
+
+/// 16 rows of six ticks, the last of which jumps back to the start.
+fn looping_player(loop_mode: LoopMode) -> Player {
+    let module = module(&[(15, 0, cell(0, 0, 0xb, 0x00))], &[0; 64], (0, 1));
+    let config = PlayerConfig {
+        loop_mode,
+        ..PlayerConfig::default()
+    };
+    Player::with_config(Arc::new(module), config)
+}
+
+#[test]
+fn stop_at_end_finishes_after_one_pass() {
+    let mut player = looping_player(LoopMode::StopAtEnd);
+    render_ticks(&mut player, 95);
+    assert!(!player.is_finished());
+    render_ticks(&mut player, 1);
+    assert!(player.is_finished());
+    let output = render_ticks(&mut player, 10);
+    assert!(output.iter().all(|&value| value == 0.0));
+}
+
+#[test]
+fn repeat_plays_the_song_again() {
+    let mut player = looping_player(LoopMode::Repeat(2));
+    render_ticks(&mut player, 3 * 96 - 1);
+    assert!(!player.is_finished());
+    assert_eq!(player.loops(), 2);
+    render_ticks(&mut player, 1);
+    assert!(player.is_finished());
+}
+
+#[test]
+fn forever_counts_loops_without_finishing() {
+    let mut player = looping_player(LoopMode::Forever);
+    render_ticks(&mut player, 5 * 96 + 1);
+    assert!(!player.is_finished());
+    assert_eq!(player.loops(), 5);
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{cell, player, C2};
+use protracktor::{Module, ModuleFormat};
+
+/// A 15 sample module with one pattern holding `cells` on channel 0 and a
+/// first sample of `length` words, looping as given in its header.
+fn soundtracker_module(
+    cells: &[(usize, [u8; 4])],
+    length: usize,
+    repeat: (usize, usize),
+) -> Module {
+    let mut data = b"soundtracker song\0\0\0".to_vec();
+    for index in 0..15 {
+        let mut header = [0; 30];
+        if index == 0 {
+            header[22..24].copy_from_slice(&(length as u16).to_be_bytes());
+            header[25] = 64;
+            header[26..28].copy_from_slice(&(repeat.0 as u16).to_be_bytes());
+            header[28..30].copy_from_slice(&(repeat.1 as u16).to_be_bytes());
+        } else {
+            header[29] = 1;
+        }
+        data.extend_from_slice(&header);
+    }
+    data.push(1);
+    data.push(120);
+    data.extend_from_slice(&[0; 128]);
+
+    let mut pattern = vec![0; 64 * 4 * 4];
+    for &(row, cell) in cells {
+        pattern[row * 16..row * 16 + 4].copy_from_slice(&cell);
+    }
+    data.extend_from_slice(&pattern);
+    data.extend(vec![0; length * 2]);
+    Module::load(&data).unwrap()
+}
+
+#[test]
+fn loop_starts_in_words_are_kept() {
+    let module = soundtracker_module(&[(0, cell(1, C2, 0, 0))], 32, (16, 16));
+    assert_eq!(module.format, ModuleFormat::Soundtracker);
+    assert_eq!(module.samples()[0].loop_start, 16);
+}
+
+#[test]
+fn loop_starts_in_bytes_mean_ultimate_soundtracker() {
+    // 32 words into a 32 word sample only fits as bytes
+    let module = soundtracker_module(&[(0, cell(1, C2, 0, 0))], 32, (32, 16));
+    assert_eq!(module.format, ModuleFormat::UltimateSoundtracker);
+    assert_eq!(module.samples()[0].loop_start, 16);
+}
+
+#[test]
+fn slides_stay_slides_without_byte_loop_starts() {
+    let module = soundtracker_module(
+        &[(0, cell(1, C2, 1, 0x03)), (1, cell(0, 0, 2, 0x30))],
+        32,
+        (0, 32),
+    );
+    assert_eq!(module.format, ModuleFormat::Soundtracker);
+    let cells: Vec<_> = (0..2)
+        .map(|row| {
+            let event = module.patterns()[0].row(row).unwrap().cell(0).unwrap();
+            (event.effect(), event.effect_param())
+        })
+        .collect();
+    assert_eq!(cells, vec![(1, 0x03), (2, 0x30)]);
+}
+
+#[test]
+fn ultimate_soundtracker_effects_are_converted() {
+    let module = soundtracker_module(
+        &[(0, cell(1, C2, 1, 0x37)), (1, cell(0, 0, 2, 0x30))],
+        32,
+        (32, 16),
+    );
+    assert_eq!(module.format, ModuleFormat::UltimateSoundtracker);
+    let cells: Vec<_> = (0..2)
+        .map(|row| {
+            let event = module.patterns()[0].row(row).unwrap().cell(0).unwrap();
+            (event.effect(), event.effect_param())
+        })
+        .collect();
+    // arpeggio, then a bend up
+    assert_eq!(cells, vec![(0, 0x37), (1, 0x03)]);
+}
+
+#[test]
+fn save_keeps_the_tempo_as_an_effect() {
+    // channel 0 already has an effect on the first row
+    let mut module = soundtracker_module(&[(0, cell(1, C2, 1, 0x03))], 32, (0, 32));
+    module.restart = 0x90;
+    assert_eq!(module.initial_bpm(), 151);
+
+    let saved = Module::load(&module.save()).unwrap();
+    assert_eq!(saved.format, ModuleFormat::ProTracker);
+    let row = saved.patterns()[0].row(0).unwrap();
+    assert_eq!(row.cell(0).unwrap().effect(), 1);
+    assert_eq!(row.cell(1).unwrap().effect(), 0xf);
+    assert_eq!(row.cell(1).unwrap().effect_param(), 151);
+    assert_eq!(
+        player(saved).duration().total,
+        player(module).duration().total
+    );
+}
+
+#[test]
+fn save_leaves_the_default_tempo_out() {
+    let module = soundtracker_module(&[(0, cell(1, C2, 0, 0))], 32, (0, 32));
+    let saved = Module::load(&module.save()).unwrap();
+    for cell in saved.patterns()[0].row(0).unwrap().cells() {
+        assert_eq!(cell.effect(), 0);
+    }
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::{cell, module, player, render_ticks, C2, C3, TICK};
+use protracktor::Player;
+use std::fs;
+use std::process::Command;
+
+/// Channel 0 plays C-2 and channel 1 C-3, both on a ramp.
+fn two_channels() -> Player {
+    let ramp: Vec<i8> = (0..64).map(|i| (i * 4 - 128) as i8).collect();
+    let cells = [(0, 0, cell(1, C2, 0, 0)), (0, 1, cell(1, C3, 0, 0))];
+    player(module(&cells, &ramp, (0, 32)))
+}
+
+#[test]
+fn stems_add_up_to_the_centred_mix() {
+    let mut player = two_channels();
+    player.set_stereo_separation(0.0);
+    let mix = render_ticks(&mut player, 6);
+
+    let mut player = two_channels();
+    let mut stems = vec![vec![0.0; 6 * TICK]; 4];
+    let mut bufs: Vec<&mut [f32]> = stems.iter_mut().map(|stem| stem.as_mut_slice()).collect();
+    player.render_channels(&mut bufs);
+    assert!(stems[2..].iter().flatten().all(|&value| value == 0.0));
+
+    // four channels share the mix, centred at half the level on each side
+    for (index, frame) in mix.chunks(2).enumerate() {
+        let sum: f32 = stems.iter().map(|stem| stem[index]).sum();
+        assert!((frame[0] - sum * 0.25).abs() < 1e-6);
+        assert!((frame[1] - sum * 0.25).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn render_channels_stops_at_the_shortest_buffer() {
+    let mut player = two_channels();
+    let mut long = vec![1.0; 2 * TICK];
+    let mut short = vec![1.0; TICK];
+    // channels without a buffer still play
+    for _ in 0..6 {
+        player.render_channels(&mut [&mut long, &mut short]);
+        assert!(long[TICK..].iter().all(|&value| value == 0.0));
+        assert!(long[..TICK].iter().any(|&value| value != 0.0));
+        assert!(short.iter().any(|&value| value != 0.0));
+    }
+    assert_eq!(player.position(), (0, 1));
+
+    player.render_channels(&mut []);
+    assert_eq!(player.position(), (0, 1));
+}
+
+#[test]
+fn cli_writes_one_wav_per_channel() {
+    let dir = std::env::temp_dir().join(format!("protracktor-stems-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(["render", "test/hbt.chip-munch.mod"])
+        .arg(dir.join("song.wav"))
+        .args(["--stems", "--seconds", "1"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    for ch in 1..=4 {
+        let wav = fs::read(dir.join(format!("song.{ch}.wav"))).unwrap();
+        // mono 16 bit at 48 kHz
+        assert_eq!(u16::from_le_bytes([wav[22], wav[23]]), 1);
+        assert_eq!(wav.len() - 44, 48000 * 2);
+    }
+    assert!(!dir.join("song.wav").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
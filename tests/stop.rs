@@ -0,0 +1,46 @@
+mod common;
+
+use common::{cell, module, player, render_ticks, C2, TICK};
+use protracktor::{LoopMode, PlayerConfig};
+use std::time::Duration;
+
+fn stopping_module() -> protracktor::Module {
+    module(
+        &[(0, 0, cell(1, C2, 0, 0)), (2, 1, cell(0, 0, 0xf, 0x00))],
+        &[64; 64],
+        (0, 32),
+    )
+}
+
+#[test]
+fn f00_ends_the_song_on_its_row() {
+    let mut player = player(stopping_module());
+    let output = render_ticks(&mut player, 20);
+    assert!(player.is_finished());
+    assert_eq!(player.position(), (0, 2));
+
+    // two rows of six ticks, then the single tick of the F00 row
+    let end = 13 * TICK * 2;
+    assert!(output[..end].iter().any(|&value| value != 0.0));
+    assert!(output[end..].iter().all(|&value| value == 0.0));
+}
+
+#[test]
+fn f00_sets_the_song_duration() {
+    let player = player(stopping_module());
+    assert_eq!(
+        player.duration().total,
+        Duration::from_millis(13 * 1000 / 50)
+    );
+}
+
+#[test]
+fn f00_stops_whatever_the_loop_mode() {
+    let mut player = player(stopping_module());
+    render_ticks(&mut player, 20);
+    player.set_config(PlayerConfig {
+        loop_mode: LoopMode::Repeat(3),
+        ..*player.config()
+    });
+    assert!(player.is_finished());
+}